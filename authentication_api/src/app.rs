@@ -0,0 +1,27 @@
+use crate::errors::api_error::ApiError;
+use crate::routes::authentication::{impersonate, login, verify_token};
+use crate::routes::invitation::{accept_invitation, invite_user};
+use crate::routes::user::{export_users, get_user, import_users, register_user};
+use actix_web::web::{JsonConfig, QueryConfig, ServiceConfig};
+
+/// Registers the API routes, the handlers, CertificateService and
+/// RegistrationSettings they use must be added as app data. Bodies and query
+/// strings that cannot be read are answered as problem+json validation errors.
+pub fn configure(cfg: &mut ServiceConfig) {
+    let json =
+        JsonConfig::default().error_handler(|e, _| ApiError::Validation(e.to_string()).into());
+    let query =
+        QueryConfig::default().error_handler(|e, _| ApiError::Validation(e.to_string()).into());
+    cfg.app_data(json)
+        .app_data(query)
+        .service(login)
+        .service(verify_token)
+        .service(impersonate)
+        .service(register_user)
+        // Registered before get_user so /user/export is not taken as an id
+        .service(export_users)
+        .service(import_users)
+        .service(get_user)
+        .service(invite_user)
+        .service(accept_invitation);
+}
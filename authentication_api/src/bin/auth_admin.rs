@@ -0,0 +1,301 @@
+use authentication_api::errors::api_error::ApiError;
+use authentication_api::handlers::user::UserHandler;
+use authentication_api::migrations::migrations;
+use authentication_api::models::user::{NewUserRequest, UserResponse};
+use authentication_api::models::user_import::UserFileFormat;
+use authentication_api::repositories::user::MongoUserRepository;
+use authentication_api::utils::key_utils::generate_key_pair;
+use authentication_api::utils::user_import::{parse_user_import, write_user_export};
+use clap::ValueEnum;
+use clap::{Parser, Subcommand};
+use common::database::migrations::MigrationRunner;
+use common::database::mongo_db::MongoDb;
+use common::models::tenant::DEFAULT_TENANT;
+use common::utils::load_settings::Settings;
+use jsonwebtoken::Algorithm;
+use serde_json::{Value, json};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+/// Administration tool for the Authentication API, all output is JSON.
+#[derive(Parser)]
+#[command(name = "auth-admin", version)]
+struct Cli {
+    /// Tenant of the user, list-users shows every tenant when omitted
+    #[arg(long, global = true)]
+    tenant: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates a new user, optionally with initial scopes, the password is read from stdin
+    CreateUser {
+        email: String,
+        /// Scope to grant, can be repeated
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+    },
+    /// Sets a new password for the user, read from stdin
+    SetPassword { email: String },
+    /// Grants a scope to the user
+    Grant { email: String, scope: String },
+    /// Revokes a scope from the user
+    Revoke { email: String, scope: String },
+    /// Lists all users
+    ListUsers,
+    /// Disables the user account
+    Disable { email: String },
+    /// Re-enables a disabled user account
+    Enable { email: String },
+    /// Imports users from a CSV (email,scopes,password_hash) or JSON Lines file
+    ImportUsers {
+        file: PathBuf,
+        /// Defaults to csv for .csv files and jsonl otherwise
+        #[arg(long)]
+        format: Option<FileFormat>,
+        /// Validate the file and report what would happen without creating users
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Exports users without password hashes
+    ExportUsers {
+        output: PathBuf,
+        #[arg(long, default_value = "jsonl")]
+        format: FileFormat,
+    },
+    /// Applies pending database migrations
+    Migrate {
+        /// Report the pending migrations and the documents they would change without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generates a fresh key pair for signing tokens
+    GenerateKeys {
+        #[arg(long, default_value = "RSAKeyStore")]
+        dir: PathBuf,
+        /// RS256, ES256 or EdDSA
+        #[arg(long, default_value = "RS256", value_parser = parse_algorithm)]
+        algorithm: Algorithm,
+        /// RSA modulus size, ignored for other algorithms
+        #[arg(long, default_value_t = 2048)]
+        bits: usize,
+        /// Overwrite an existing key pair
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::init();
+    let cli = Cli::parse();
+
+    match run(cli.tenant, cli.command).await {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", json!({ "error": e.to_string(), "code": e.code() }));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(tenant: Option<String>, command: Command) -> Result<Value, ApiError> {
+    if let Command::GenerateKeys {
+        dir,
+        algorithm,
+        bits,
+        force,
+    } = command
+    {
+        return generate_keys(dir, algorithm, bits, force);
+    }
+
+    let settings = Settings::load().map_err(|e| ApiError::Internal(e.to_string()))?;
+    let mongo_db = MongoDb::connect(&settings.database)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    if let Command::Migrate { dry_run } = command {
+        let runner = MigrationRunner::new(mongo_db.database, migrations(), &settings.migrations)
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+        let report = if dry_run {
+            runner.dry_run().await
+        } else {
+            runner.run().await
+        }
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+        return to_json(&report);
+    }
+    let user_handler = UserHandler::new(
+        Arc::new(MongoUserRepository::new(&mongo_db.database)),
+        settings.password,
+    );
+    let tenant_id = tenant.as_deref().unwrap_or(DEFAULT_TENANT);
+    match command {
+        Command::CreateUser { email, scopes } => {
+            let password = read_password()?;
+            let mut user = user_handler
+                .create_user(NewUserRequest {
+                    tenant_id: tenant_id.to_string(),
+                    email,
+                    password,
+                })
+                .await?;
+            if !scopes.is_empty() {
+                user = user_handler
+                    .update_user_scopes(&user_id(&user)?, scopes)
+                    .await?;
+            }
+            to_json(&user)
+        }
+        Command::SetPassword { email } => {
+            let password = read_password()?;
+            let id = find_user_id(&user_handler, tenant_id, &email).await?;
+            to_json(&user_handler.update_user_password(&id, &password).await?)
+        }
+        Command::Grant { email, scope } => {
+            let id = find_user_id(&user_handler, tenant_id, &email).await?;
+            to_json(&user_handler.grant_user_scope(&id, &scope).await?)
+        }
+        Command::Revoke { email, scope } => {
+            let id = find_user_id(&user_handler, tenant_id, &email).await?;
+            to_json(&user_handler.revoke_user_scope(&id, &scope).await?)
+        }
+        Command::ListUsers => to_json(&user_handler.list_users(tenant.as_deref()).await?),
+        Command::Disable { email } => {
+            let id = find_user_id(&user_handler, tenant_id, &email).await?;
+            to_json(&user_handler.set_user_disabled(&id, true).await?)
+        }
+        Command::Enable { email } => {
+            let id = find_user_id(&user_handler, tenant_id, &email).await?;
+            to_json(&user_handler.set_user_disabled(&id, false).await?)
+        }
+        Command::ImportUsers {
+            file,
+            format,
+            dry_run,
+        } => {
+            let format = format.map(UserFileFormat::from).unwrap_or_else(|| {
+                match file.extension().and_then(|extension| extension.to_str()) {
+                    Some("csv") => UserFileFormat::Csv,
+                    _ => UserFileFormat::Jsonl,
+                }
+            });
+            let data = std::fs::read(&file).map_err(|e| {
+                ApiError::Validation(format!("Failed to read {}: {}", file.display(), e))
+            })?;
+            let records = parse_user_import(format, &data);
+            to_json(
+                &user_handler
+                    .import_users(tenant_id, None, records, dry_run)
+                    .await,
+            )
+        }
+        Command::ExportUsers { output, format } => {
+            let users = user_handler.list_users(Some(tenant_id)).await?;
+            let data = write_user_export(format.into(), &users)
+                .map_err(|e| ApiError::Internal(format!("Export error: {}", e)))?;
+            std::fs::write(&output, data).map_err(|e| {
+                ApiError::Internal(format!("Failed to write {}: {}", output.display(), e))
+            })?;
+            Ok(json!({ "exported": users.len(), "output": output }))
+        }
+        Command::Migrate { .. } => unreachable!("handled before creating the handler"),
+        Command::GenerateKeys { .. } => unreachable!("handled before connecting"),
+    }
+}
+
+/// Writes private_key.pem and public_key.pem into the key store directory
+fn generate_keys(
+    dir: PathBuf,
+    algorithm: Algorithm,
+    bits: usize,
+    force: bool,
+) -> Result<Value, ApiError> {
+    let private_key_path = dir.join("private_key.pem");
+    let public_key_path = dir.join("public_key.pem");
+
+    if !force && (private_key_path.exists() || public_key_path.exists()) {
+        return Err(ApiError::Conflict(format!(
+            "Key pair already exists in {}, use --force to replace it",
+            dir.display()
+        )));
+    }
+
+    generate_key_pair(algorithm, &private_key_path, &public_key_path, bits)
+        .map_err(|e| ApiError::Internal(format!("Key generation error: {}", e)))?;
+
+    Ok(json!({
+        "algorithm": algorithm,
+        "private_key": private_key_path,
+        "public_key": public_key_path,
+    }))
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FileFormat {
+    Csv,
+    Jsonl,
+}
+
+impl From<FileFormat> for UserFileFormat {
+    fn from(format: FileFormat) -> Self {
+        match format {
+            FileFormat::Csv => UserFileFormat::Csv,
+            FileFormat::Jsonl => UserFileFormat::Jsonl,
+        }
+    }
+}
+
+fn parse_algorithm(value: &str) -> Result<Algorithm, String> {
+    match value.parse::<Algorithm>() {
+        Ok(algorithm @ (Algorithm::RS256 | Algorithm::ES256 | Algorithm::EdDSA)) => Ok(algorithm),
+        _ => Err(format!("unsupported algorithm {}", value)),
+    }
+}
+
+/// Reads the password from the first line of stdin, so it does not show up
+/// in the process list or shell history like an argument would
+fn read_password() -> Result<String, ApiError> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+    }
+    let mut line = String::new();
+    stdin
+        .read_line(&mut line)
+        .map_err(|e| ApiError::Internal(format!("Failed to read the password: {}", e)))?;
+    let password = line.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err(ApiError::Validation(
+            "Password must be given on stdin".to_string(),
+        ));
+    }
+    Ok(password.to_string())
+}
+
+async fn find_user_id(
+    user_handler: &UserHandler,
+    tenant_id: &str,
+    email: &str,
+) -> Result<String, ApiError> {
+    let user = user_handler.find_user_by_email(tenant_id, email).await?;
+    user_id(&user)
+}
+
+fn user_id(user: &UserResponse) -> Result<String, ApiError> {
+    user.id
+        .map(|id| id.to_hex())
+        .ok_or_else(|| ApiError::Internal("User has no id".to_string()))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Value, ApiError> {
+    serde_json::to_value(value)
+        .map_err(|e| ApiError::Internal(format!("Serialization error: {}", e)))
+}
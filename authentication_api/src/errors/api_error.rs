@@ -0,0 +1,93 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use common::errors::problem::ProblemDetails;
+use common::utils::password_utils::PasswordError;
+use log::error;
+use std::fmt;
+
+/// Errors returned by the authentication service, rendered as problem+json responses
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(String),
+    Conflict(String),
+    Validation(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Upstream(String),
+    Internal(String),
+}
+
+impl ApiError {
+    /// Stable error code returned in the `code` member of the problem body
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::Upstream(_) => "upstream_unavailable",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::NotFound(detail)
+            | ApiError::Conflict(detail)
+            | ApiError::Validation(detail)
+            | ApiError::Unauthorized(detail)
+            | ApiError::Forbidden(detail)
+            | ApiError::Upstream(detail)
+            | ApiError::Internal(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        // Internal details such as database errors are logged, not returned
+        let detail = match self {
+            ApiError::Internal(detail) => {
+                error!("Internal error: {}", detail);
+                "An unexpected error occurred".to_string()
+            }
+            _ => self.to_string(),
+        };
+        ProblemDetails::new(self.status_code(), self.code(), detail).to_response()
+    }
+}
+
+impl From<mongodb::error::Error> for ApiError {
+    fn from(e: mongodb::error::Error) -> Self {
+        ApiError::Internal(format!("DatabaseError: {}", e))
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for ApiError {
+    fn from(e: mongodb::bson::ser::Error) -> Self {
+        ApiError::Internal(format!("Serialization error: {}", e))
+    }
+}
+
+impl From<PasswordError> for ApiError {
+    fn from(e: PasswordError) -> Self {
+        ApiError::Internal(format!("Password hashing error: {}", e))
+    }
+}
@@ -1,62 +1,196 @@
+use crate::errors::api_error::ApiError;
+use crate::models::audit::AuditEvent;
 use crate::models::authentication::{Login, LoginResponse};
-use crate::services::certification::CertificateService;
-use crate::services::user::UserService;
-use crate::utils::password_utils::verify_password;
+use crate::repositories::audit::AuditRepository;
+use crate::repositories::user::UserRepository;
+use common::services::certification::{Actor, CertificateService, Claims};
+use common::utils::load_settings::PasswordSettings;
+use common::utils::password_utils::{has_password, hash_password, needs_rehash, verify_password};
+use log::warn;
+use mongodb::bson::{DateTime, doc};
+use prometheus::{IntCounterVec, register_int_counter_vec};
+use std::sync::{Arc, LazyLock};
+
+/// Scope required to impersonate another user, never carried into impersonation tokens
+pub const IMPERSONATE_SCOPE: &str = "user:impersonate";
+
+/// Login attempts by result, failures are labelled with the error code
+static LOGIN_ATTEMPTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "auth_login_attempts_total",
+        "Login attempts by result, success or the error code",
+        &["result"]
+    )
+    .expect("Failed to register auth_login_attempts_total")
+});
 
 pub struct AuthenticationHandler {
-    user_service: UserService,
+    user_repository: Arc<dyn UserRepository>,
     certificate_service: CertificateService,
+    audit_repository: Arc<dyn AuditRepository>,
+    password_settings: PasswordSettings,
 }
 
-/// Initializes a new instance of the AuthenticationHandler.
+/// Initializes a new instance of the AuthenticationHandler,
+/// the certificate service must be able to sign tokens.
 impl AuthenticationHandler {
-    pub async fn new() -> Self {
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        audit_repository: Arc<dyn AuditRepository>,
+        certificate_service: CertificateService,
+        password_settings: PasswordSettings,
+    ) -> Self {
         AuthenticationHandler {
-            user_service: UserService::new().await,
-            certificate_service: CertificateService::new(
-                "RSAKeyStore/private_key.pem",
-                "RSAKeyStore/public_key.pem",
-            )
-            .expect("Failed to create CertificateService"),
+            user_repository,
+            certificate_service,
+            audit_repository,
+            password_settings,
         }
     }
 
     /// Logs in a user with the provided credentials.
-    pub async fn login_user(&self, login: Login) -> Result<LoginResponse, String> {
-        match self.user_service.find_by_email(login.email.as_str()).await {
-            Ok(Some(user)) => {
-                let user_hash = user.password.clone();
-
-                match verify_password(login.password.as_str(), &user_hash) {
-                    Ok(is_valid) => {
-                        if is_valid {
-                            let jwt_token = self.certificate_service.create_token(
-                                &user.id.unwrap().to_string(),
-                                Some(user.email),
-                                user.scopes,
-                            );
-                            Ok(LoginResponse {
-                                token: jwt_token.unwrap(),
-                                refresh_token: "refresh_token".to_string(),
-                                message: "Successfully logged in".to_string(),
-                            })
-                        } else {
-                            Err("Invalid credentials".to_string())
-                        }
-                    }
-                    Err(e) => Err(format!("Password verification error: {}", e)),
-                }
+    pub async fn login_user(&self, login: Login) -> Result<LoginResponse, ApiError> {
+        let result = self.authenticate(login).await;
+        let label = match &result {
+            Ok(_) => "success",
+            Err(e) => e.code(),
+        };
+        LOGIN_ATTEMPTS.with_label_values(&[label]).inc();
+        result
+    }
+
+    async fn authenticate(&self, login: Login) -> Result<LoginResponse, ApiError> {
+        let user = self
+            .user_repository
+            .find_by_email(&login.tenant_id, &login.email)
+            .await?
+            .ok_or_else(|| ApiError::Unauthorized("Invalid credentials".to_string()))?;
+        // Imported users without a password are refused like a wrong password
+        if !has_password(&user.password)
+            || !verify_password(login.password.as_str(), &user.password)?
+        {
+            return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+        }
+        // Only checked after the password so it does not reveal the account to anyone
+        if user.disabled {
+            return Err(ApiError::Forbidden("Account disabled".to_string()));
+        }
+        if needs_rehash(&user.password, &self.password_settings) {
+            self.rehash_password(&user.id.unwrap().to_hex(), &login.password)
+                .await;
+        }
+        let jwt_token = self
+            .certificate_service
+            .create_token(
+                &user.id.unwrap().to_string(),
+                &user.tenant_id,
+                Some(user.email),
+                user.scopes,
+            )
+            .map_err(|e| ApiError::Internal(format!("Token creation error: {}", e)))?;
+        Ok(LoginResponse {
+            token: jwt_token,
+            refresh_token: "refresh_token".to_string(),
+            message: "Successfully logged in".to_string(),
+        })
+    }
+
+    /// Replaces a legacy or outdated password hash with an Argon2id hash using
+    /// the configured parameters, failures are logged and do not fail the login.
+    async fn rehash_password(&self, id: &str, password: &str) {
+        let password_hash = match hash_password(password, &self.password_settings) {
+            Ok(password_hash) => password_hash,
+            Err(e) => {
+                warn!("Failed to rehash password for user {}: {}", id, e);
+                return;
             }
-            Ok(None) => Err("User not found".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
+        };
+        let update_doc = doc! {"password": password_hash, "updated_at": DateTime::now()};
+        if let Err(e) = self.user_repository.update_user(id, update_doc).await {
+            warn!("Failed to store rehashed password for user {}: {}", id, e);
+        }
+    }
+
+    /// Issues a short lived token for the target user on behalf of an admin,
+    /// the admin is recorded in the `act` claim and in the audit log.
+    /// Users holding scopes the admin does not hold cannot be impersonated.
+    pub async fn impersonate_user(
+        &self,
+        admin: &Claims,
+        target_id: &str,
+    ) -> Result<LoginResponse, ApiError> {
+        if admin.is_impersonated() {
+            return Err(ApiError::Forbidden(
+                "Cannot impersonate from an impersonation token".to_string(),
+            ));
+        }
+        if admin.sub == target_id {
+            return Err(ApiError::Validation(
+                "Cannot impersonate yourself".to_string(),
+            ));
         }
+
+        // Users of other tenants are reported as not found
+        let user = self
+            .user_repository
+            .find_by_id(target_id)
+            .await?
+            .filter(|user| user.tenant_id == admin.tenant_id)
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+        if user.disabled {
+            return Err(ApiError::Forbidden("Account disabled".to_string()));
+        }
+        if let Some(scope) = user
+            .scopes
+            .iter()
+            .filter(|scope| *scope != IMPERSONATE_SCOPE)
+            .find(|scope| !admin.scopes.contains(scope))
+        {
+            return Err(ApiError::Forbidden(format!(
+                "Cannot impersonate a user holding scope {} not held by the admin",
+                scope
+            )));
+        }
+
+        // Record before issuing, no impersonation token exists without an audit entry
+        let event = AuditEvent::create_new(
+            &admin.tenant_id,
+            "user.impersonate",
+            &admin.sub,
+            admin.email.clone(),
+            Some(target_id.to_string()),
+            doc! {"target_email": &user.email},
+        );
+        self.audit_repository.record(event).await?;
+
+        let scopes = user
+            .scopes
+            .into_iter()
+            .filter(|scope| scope != IMPERSONATE_SCOPE)
+            .collect();
+        let actor = Actor {
+            sub: admin.sub.clone(),
+            email: admin.email.clone(),
+        };
+        let jwt_token = self
+            .certificate_service
+            .create_impersonation_token(target_id, &user.tenant_id, Some(user.email), scopes, actor)
+            .map_err(|e| ApiError::Internal(format!("Token creation error: {}", e)))?;
+        Ok(LoginResponse {
+            token: jwt_token,
+            refresh_token: String::new(),
+            message: "Impersonation token issued".to_string(),
+        })
     }
 
     /// Verifies a user's token.
-    pub async fn verify_token(&self, token: &str) -> Result<bool, String> {
+    pub async fn verify_token(&self, token: &str) -> Result<bool, ApiError> {
         match self.certificate_service.verify_token(token) {
             Ok(result) => Ok(true),
-            Err(e) => Err(format!("Token verification error: {}", e)),
+            Err(e) => Err(ApiError::Unauthorized(format!(
+                "Token verification error: {}",
+                e
+            ))),
         }
     }
 }
@@ -0,0 +1,126 @@
+use crate::errors::api_error::ApiError;
+use crate::models::invitation::{
+    AcceptInvitationRequest, Invitation, InvitationResponse, NewInvitationRequest,
+};
+use crate::models::user::{NewUserRequest, User, UserResponse};
+use crate::repositories::invitation::InvitationRepository;
+use crate::repositories::user::UserRepository;
+use common::utils::load_settings::{PasswordSettings, RegistrationSettings};
+use common::utils::password_utils::hash_password;
+use log::warn;
+use mongodb::bson::DateTime;
+use rand::Rng;
+use std::sync::Arc;
+
+pub struct InvitationHandler {
+    invitation_repository: Arc<dyn InvitationRepository>,
+    user_repository: Arc<dyn UserRepository>,
+    expiry_hours: i64,
+    max_expiry_hours: i64,
+    password_settings: PasswordSettings,
+}
+
+/// Implementation of InvitationHandler
+impl InvitationHandler {
+    pub fn new(
+        invitation_repository: Arc<dyn InvitationRepository>,
+        user_repository: Arc<dyn UserRepository>,
+        registration_settings: &RegistrationSettings,
+        password_settings: PasswordSettings,
+    ) -> Self {
+        InvitationHandler {
+            invitation_repository,
+            user_repository,
+            expiry_hours: registration_settings.invitation_expiry_hours,
+            max_expiry_hours: registration_settings.max_invitation_expiry_hours,
+            password_settings,
+        }
+    }
+
+    /// Invites a user by email into the inviter's tenant with a predefined set of scopes
+    pub async fn invite_user(
+        &self,
+        tenant_id: &str,
+        invited_by: &str,
+        request: NewInvitationRequest,
+    ) -> Result<InvitationResponse, ApiError> {
+        if self
+            .user_repository
+            .find_by_email(tenant_id, &request.email)
+            .await?
+            .is_some()
+        {
+            return Err(ApiError::Conflict(format!(
+                "User {} already exists",
+                request.email
+            )));
+        }
+
+        let expires_in_hours = request.expires_in_hours.unwrap_or(self.expiry_hours);
+        if expires_in_hours <= 0 || expires_in_hours > self.max_expiry_hours {
+            return Err(ApiError::Validation(format!(
+                "Invitation expiry must be between 1 and {} hours",
+                self.max_expiry_hours
+            )));
+        }
+        let expires_at = expires_in_hours
+            .checked_mul(60 * 60 * 1000)
+            .and_then(|millis| DateTime::now().timestamp_millis().checked_add(millis))
+            .map(DateTime::from_millis)
+            .ok_or_else(|| ApiError::Validation("Invitation expiry is out of range".to_string()))?;
+
+        let invitation =
+            Invitation::create_new(tenant_id, invited_by, request, generate_token(), expires_at);
+        let invitation = self
+            .invitation_repository
+            .create_invitation(invitation)
+            .await?;
+        Ok(invitation.to_invitation_response())
+    }
+
+    /// Accepts an invitation, creating the user with the invited scopes and chosen password
+    pub async fn accept_invitation(
+        &self,
+        request: AcceptInvitationRequest,
+    ) -> Result<UserResponse, ApiError> {
+        let invitation = self
+            .invitation_repository
+            .find_by_token(&request.token)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Invitation not found".to_string()))?;
+        if invitation.accepted_at.is_some() {
+            return Err(ApiError::Conflict(
+                "Invitation already accepted".to_string(),
+            ));
+        }
+        if invitation.is_expired() {
+            return Err(ApiError::Validation("Invitation expired".to_string()));
+        }
+
+        let password = hash_password(&request.password, &self.password_settings)?;
+        let mut user = User::create_new(NewUserRequest {
+            tenant_id: invitation.tenant_id,
+            email: invitation.email,
+            password,
+        });
+        user.scopes = invitation.scopes;
+
+        // Create the user before claiming the invitation, a failed create leaves the
+        // invitation usable and the unique email keeps concurrent accepts to one user
+        let user = self.user_repository.create_user(user).await?;
+        if !self
+            .invitation_repository
+            .mark_accepted(&request.token)
+            .await?
+        {
+            warn!("Invitation for {} was accepted concurrently", user.email);
+        }
+        Ok(user.to_user_response())
+    }
+}
+
+/// Generates a random 256 bit hex token for the invitation link
+fn generate_token() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
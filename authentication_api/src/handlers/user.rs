@@ -1,45 +1,66 @@
+use crate::errors::api_error::ApiError;
 use crate::models::user::{NewUserRequest, User, UserResponse};
-use crate::services::user::UserService;
-use crate::utils::password_utils::verify_password;
+use crate::models::user_import::{
+    ImportReport, ImportRowResult, ImportRowStatus, ImportUserRecord,
+};
+use crate::repositories::user::UserRepository;
+use common::services::certification::Claims;
+use common::utils::load_settings::PasswordSettings;
+use common::utils::password_utils::{
+    NO_PASSWORD, has_password, hash_password, is_supported_hash, needs_rehash, verify_password,
+};
+use log::warn;
 use mongodb::bson::{DateTime, Document};
+use std::collections::HashSet;
+use std::sync::Arc;
 
 pub struct UserHandler {
-    user_service: UserService,
+    user_repository: Arc<dyn UserRepository>,
+    password_settings: PasswordSettings,
 }
 
 /// Implementation of UserHandler
 impl UserHandler {
-    pub async fn new() -> Self {
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        password_settings: PasswordSettings,
+    ) -> Self {
         Self {
-            user_service: UserService::new().await,
+            user_repository,
+            password_settings,
         }
     }
-    /// Create a new user
-    pub async fn create_user(&self, user_request: NewUserRequest) -> Result<UserResponse, String> {
+    /// Create a new user, the password is stored as an Argon2 hash
+    pub async fn create_user(
+        &self,
+        mut user_request: NewUserRequest,
+    ) -> Result<UserResponse, ApiError> {
+        user_request.password = hash_password(&user_request.password, &self.password_settings)?;
         let new_user = User::create_new(user_request);
-        match self.user_service.create_user(new_user).await {
-            Ok(Some(user)) => Ok(user.to_user_response()),
-            Ok(None) => Err("User Not Created".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
-        }
+        let user = self.user_repository.create_user(new_user).await?;
+        Ok(user.to_user_response())
     }
 
     //// Get a user by id
-    pub async fn find_user_by_id(&self, id: &str) -> Result<UserResponse, String> {
-        match self.user_service.find_by_id(id).await {
-            Ok(Some(user)) => Ok(user.to_user_response()),
-            Ok(None) => Err("User not found".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
-        }
+    pub async fn find_user_by_id(&self, id: &str) -> Result<UserResponse, ApiError> {
+        self.user_repository
+            .find_by_id(id)
+            .await?
+            .map(|user| user.to_user_response())
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))
     }
 
-    ////Get user by email
-    pub async fn find_user_by_email(&self, email: &str) -> Result<UserResponse, String> {
-        match self.user_service.find_by_email(email).await {
-            Ok(Some(user)) => Ok(user.to_user_response()),
-            Ok(None) => Err("User not found".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
-        }
+    ////Get user by email within a tenant
+    pub async fn find_user_by_email(
+        &self,
+        tenant_id: &str,
+        email: &str,
+    ) -> Result<UserResponse, ApiError> {
+        self.user_repository
+            .find_by_email(tenant_id, email)
+            .await?
+            .map(|user| user.to_user_response())
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))
     }
 
     //// Updates the user scopes
@@ -47,52 +68,196 @@ impl UserHandler {
         &self,
         id: &str,
         scopes: Vec<String>,
-    ) -> Result<UserResponse, String> {
+    ) -> Result<UserResponse, ApiError> {
         let mut update_doc = Document::new();
-        update_doc.insert("scopes", mongodb::bson::to_bson(&scopes).unwrap());
+        update_doc.insert("scopes", mongodb::bson::to_bson(&scopes)?);
         update_doc.insert("updated_at", DateTime::now());
-        match self.user_service.update_user(id, update_doc).await {
-            Ok(Some(user)) => Ok(user.to_user_response()),
-            Ok(None) => Err("User not found".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
+        let user = self.user_repository.update_user(id, update_doc).await?;
+        Ok(user.to_user_response())
+    }
+
+    //// Lists all users, or only the users of one tenant
+    pub async fn list_users(&self, tenant_id: Option<&str>) -> Result<Vec<UserResponse>, ApiError> {
+        let users = self.user_repository.list_users(tenant_id).await?;
+        Ok(users.iter().map(|user| user.to_user_response()).collect())
+    }
+
+    //// Adds a scope to the user, granting an existing scope is a no-op
+    pub async fn grant_user_scope(&self, id: &str, scope: &str) -> Result<UserResponse, ApiError> {
+        let user = self.find_user_by_id(id).await?;
+        let mut scopes = user.scopes;
+        if !scopes.iter().any(|s| s == scope) {
+            scopes.push(scope.to_string());
+        }
+        self.update_user_scopes(id, scopes).await
+    }
+
+    //// Removes a scope from the user
+    pub async fn revoke_user_scope(&self, id: &str, scope: &str) -> Result<UserResponse, ApiError> {
+        let user = self.find_user_by_id(id).await?;
+        let scopes = user.scopes.into_iter().filter(|s| s != scope).collect();
+        self.update_user_scopes(id, scopes).await
+    }
+
+    /// Imports users into a tenant, rows are validated individually and users whose
+    /// email already exists are skipped so an import can safely be re-run.
+    /// A dry run validates every row without writing anything. Rows may only grant
+    /// scopes the caller holds, `None` is the admin CLI which may grant any scope.
+    pub async fn import_users(
+        &self,
+        tenant_id: &str,
+        caller: Option<&Claims>,
+        records: Vec<Result<ImportUserRecord, String>>,
+        dry_run: bool,
+    ) -> ImportReport {
+        let mut seen_emails = HashSet::new();
+        let mut rows = Vec::with_capacity(records.len());
+
+        for (index, record) in records.into_iter().enumerate() {
+            let row = index + 1;
+            let result = match record {
+                Ok(record) => {
+                    self.import_user(tenant_id, caller, record, dry_run, &mut seen_emails)
+                        .await
+                }
+                Err(e) => (None, ImportRowStatus::Failed, Some(e)),
+            };
+            let (email, status, message) = result;
+            rows.push(ImportRowResult {
+                row,
+                email,
+                status,
+                message,
+            });
+        }
+
+        ImportReport::new(dry_run, rows)
+    }
+
+    async fn import_user(
+        &self,
+        tenant_id: &str,
+        caller: Option<&Claims>,
+        record: ImportUserRecord,
+        dry_run: bool,
+        seen_emails: &mut HashSet<String>,
+    ) -> (Option<String>, ImportRowStatus, Option<String>) {
+        let email = record.email.trim().to_string();
+        let failed =
+            |message: String| (Some(email.clone()), ImportRowStatus::Failed, Some(message));
+
+        if email.is_empty() || !email.contains('@') {
+            return failed("Invalid email".to_string());
+        }
+        if record
+            .scopes
+            .iter()
+            .any(|scope| scope.is_empty() || scope.contains(char::is_whitespace))
+        {
+            return failed("Invalid scope".to_string());
+        }
+        if let Some(caller) = caller
+            && let Some(scope) = record
+                .scopes
+                .iter()
+                .find(|scope| !caller.scopes.contains(scope))
+        {
+            return failed(format!("Cannot grant scope {} not held by importer", scope));
         }
+        if let Some(hash) = &record.password_hash
+            && !is_supported_hash(hash)
+        {
+            return failed("Unsupported password hash, expected Argon2 or bcrypt".to_string());
+        }
+        if !seen_emails.insert(email.clone()) {
+            return (
+                Some(email),
+                ImportRowStatus::Skipped,
+                Some("Duplicate email in import".to_string()),
+            );
+        }
+        match self.user_repository.find_by_email(tenant_id, &email).await {
+            Ok(Some(_)) => {
+                return (
+                    Some(email),
+                    ImportRowStatus::Skipped,
+                    Some("User already exists".to_string()),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => return failed(e.to_string()),
+        }
+        if dry_run {
+            return (Some(email), ImportRowStatus::Valid, None);
+        }
+
+        let mut user = User::create_new(NewUserRequest {
+            tenant_id: tenant_id.to_string(),
+            email: email.clone(),
+            password: record
+                .password_hash
+                .unwrap_or_else(|| NO_PASSWORD.to_string()),
+        });
+        user.scopes = record.scopes;
+        match self.user_repository.create_user(user).await {
+            Ok(_) => (Some(email), ImportRowStatus::Created, None),
+            Err(e) => failed(e.to_string()),
+        }
+    }
+
+    /// Disables or re-enables the user account, disabled users cannot log in
+    pub async fn set_user_disabled(
+        &self,
+        id: &str,
+        disabled: bool,
+    ) -> Result<UserResponse, ApiError> {
+        let mut update_doc = Document::new();
+        update_doc.insert("disabled", disabled);
+        update_doc.insert("updated_at", DateTime::now());
+        let user = self.user_repository.update_user(id, update_doc).await?;
+        Ok(user.to_user_response())
     }
 
-    /// Updates the user password
+    /// Updates the user password, the password is stored as an Argon2 hash
     pub async fn update_user_password(
         &self,
         id: &str,
         password: &str,
-    ) -> Result<UserResponse, String> {
+    ) -> Result<UserResponse, ApiError> {
+        let password_hash = hash_password(password, &self.password_settings)?;
         let mut update_doc = Document::new();
-        update_doc.insert("password", mongodb::bson::to_bson(password).unwrap());
+        update_doc.insert("password", password_hash);
         update_doc.insert("updated_at", DateTime::now());
-        match self.user_service.update_user(id, update_doc).await {
-            Ok(Some(user)) => Ok(user.to_user_response()),
-            Ok(None) => Err("User not found".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
-        }
+        let user = self.user_repository.update_user(id, update_doc).await?;
+        Ok(user.to_user_response())
     }
 
-    /// Logs in a user by email and password
-    pub async fn login_user(&self, email: &str, password: &str) -> Result<UserResponse, String> {
-        match self.user_service.find_by_email(email).await {
-            Ok(Some(user)) => {
-                let user_hash = user.password.clone();
-
-                match verify_password(password, &user_hash) {
-                    Ok(is_valid) => {
-                        if is_valid {
-                            Ok(user.to_user_response())
-                        } else {
-                            Err("Invalid credentials".to_string())
-                        }
-                    }
-                    Err(e) => Err(format!("Password verification error: {}", e)),
-                }
+    /// Logs in a user of a tenant by email and password, unknown users and
+    /// wrong passwords are both reported as invalid credentials
+    pub async fn login_user(
+        &self,
+        tenant_id: &str,
+        email: &str,
+        password: &str,
+    ) -> Result<UserResponse, ApiError> {
+        let user = self
+            .user_repository
+            .find_by_email(tenant_id, email)
+            .await?
+            .ok_or_else(|| ApiError::Unauthorized("Invalid credentials".to_string()))?;
+        if !has_password(&user.password) || !verify_password(password, &user.password)? {
+            return Err(ApiError::Unauthorized("Invalid credentials".to_string()));
+        }
+        // Only checked after the password so it does not reveal the account to anyone
+        if user.disabled {
+            return Err(ApiError::Forbidden("Account disabled".to_string()));
+        }
+        if needs_rehash(&user.password, &self.password_settings) {
+            let id = user.id.unwrap().to_hex();
+            if let Err(e) = self.update_user_password(&id, password).await {
+                warn!("Failed to rehash password for user {}: {}", id, e);
             }
-            Ok(None) => Err("User not found".to_string()),
-            Err(e) => Err(format!("DatabaseError: {}", e)),
         }
+        Ok(user.to_user_response())
     }
 }
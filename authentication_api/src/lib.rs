@@ -1,32 +1,43 @@
 #![allow(unused)]
 
-pub mod database {
-    pub mod mongo_db;
+pub mod app;
+pub mod migrations;
+
+pub mod errors {
+    pub mod api_error;
 }
 
 pub mod utils {
-    pub mod load_settings;
-    pub mod password_utils;
+    pub mod key_utils;
+    pub mod user_import;
 }
 
 pub mod models {
+    pub mod audit;
     pub mod authentication;
+    pub mod invitation;
     pub mod user;
+    pub mod user_import;
 }
 
-pub mod services {
-    pub mod certification;
+pub mod repositories {
+    pub mod audit;
+    pub mod in_memory;
+    pub mod invitation;
     pub mod user;
 }
 
 pub mod handlers {
 
     pub mod authentication;
+    pub mod invitation;
     pub mod user;
 }
 
 pub mod routes {
 
     pub mod authentication;
+    pub mod invitation;
+    pub mod openapi;
     pub mod user;
 }
@@ -1,43 +1,127 @@
 use actix_web::middleware::Logger;
 use actix_web::{App, HttpServer, web};
+use authentication_api::app::configure;
 use authentication_api::handlers::authentication::AuthenticationHandler;
+use authentication_api::handlers::invitation::InvitationHandler;
 use authentication_api::handlers::user::UserHandler;
-use authentication_api::routes::authentication::{login, verify_token};
-use authentication_api::routes::user::{get_user, register_user};
-use authentication_api::services::certification::CertificateService;
+use authentication_api::migrations::migrations;
+use authentication_api::repositories::audit::MongoAuditRepository;
+use authentication_api::repositories::invitation::MongoInvitationRepository;
+use authentication_api::repositories::user::MongoUserRepository;
+use authentication_api::routes::openapi::ApiDoc;
+use common::database::indexes::ensure_indexes;
+use common::database::migrations::MigrationRunner;
+use common::database::mongo_db::MongoDb;
+use common::middleware::metrics::RequestMetrics;
+use common::middleware::request_tracing::{LOG_FORMAT, RequestTracing};
+use common::routes::health::{live, ready};
+use common::routes::metrics::metrics;
+use common::services::certification::CertificateService;
+use common::services::health::HealthService;
+use common::utils::load_settings::Settings;
+use common::utils::retry::retry_with_backoff;
+use common::utils::shutdown::stop_server_on_signal;
+use common::utils::telemetry::init_tracer;
+use env_logger::Env;
+use log::warn;
+use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init();
+    let settings = Settings::load().map_err(std::io::Error::other)?;
+    env_logger::Builder::from_env(Env::default().default_filter_or(&settings.logging.level)).init();
+    let tracer_provider =
+        init_tracer(&settings.tracing, "authentication_api").map_err(std::io::Error::other)?;
 
-    let auth_handler = AuthenticationHandler::new().await;
-    let user_handler = UserHandler::new().await;
-    let cert_handler =
-        CertificateService::new("RSAKeyStore/private_key.pem", "RSAKeyStore/public_key.pem")
-            .expect("Failed to create CertificateService");
+    let mongo_db = MongoDb::connect(&settings.database)
+        .await
+        .map_err(std::io::Error::other)?;
+    retry_with_backoff("MongoDB", &settings.startup, || mongo_db.ping())
+        .await
+        .map_err(std::io::Error::other)?;
+    // Migrations run before the indexes are ensured so they can fix data a new index relies on
+    if settings.migrations.run_on_startup {
+        MigrationRunner::new(
+            mongo_db.database.clone(),
+            migrations(),
+            &settings.migrations,
+        )
+        .map_err(std::io::Error::other)?
+        .run()
+        .await
+        .map_err(std::io::Error::other)?;
+    }
+    let indexes = [
+        MongoUserRepository::indexes(),
+        MongoInvitationRepository::indexes(),
+    ]
+    .concat();
+    ensure_indexes(&mongo_db.database, &indexes)
+        .await
+        .map_err(std::io::Error::other)?;
+
+    // Every repository shares the one client and its connection pool
+    let user_repository = Arc::new(MongoUserRepository::new(&mongo_db.database));
+    let auth_handler = AuthenticationHandler::new(
+        user_repository.clone(),
+        Arc::new(MongoAuditRepository::new(&mongo_db.database)),
+        CertificateService::signing_from_settings(&settings.jwt)
+            .expect("Failed to create CertificateService"),
+        settings.password.clone(),
+    );
+    let user_handler = UserHandler::new(user_repository.clone(), settings.password.clone());
+    let invitation_handler = InvitationHandler::new(
+        Arc::new(MongoInvitationRepository::new(&mongo_db.database)),
+        user_repository,
+        &settings.registration,
+        settings.password.clone(),
+    );
+    let cert_handler = CertificateService::signing_from_settings(&settings.jwt)
+        .expect("Failed to create CertificateService");
+    let health_service = HealthService::new(Some(mongo_db.database.clone()), None);
 
     let handler_data = web::Data::new(auth_handler);
     let user_data = web::Data::new(user_handler);
+    let invitation_data = web::Data::new(invitation_handler);
     let cert_service = web::Data::new(cert_handler);
+    let registration_settings = web::Data::new(settings.registration);
+    let health_data = web::Data::new(health_service);
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(Logger::default())
+            .wrap(RequestTracing)
+            .wrap(Logger::new(LOG_FORMAT))
+            .wrap(RequestMetrics)
             .app_data(handler_data.clone())
             .app_data(user_data.clone())
+            .app_data(invitation_data.clone())
             .app_data(cert_service.clone())
-            .service(login)
-            .service(verify_token)
-            .service(register_user)
-            .service(get_user)
+            .app_data(registration_settings.clone())
+            .app_data(health_data.clone())
+            .service(live)
+            .service(ready)
+            .service(metrics)
+            .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/openapi.json", ApiDoc::openapi()))
+            .configure(configure)
     })
-    .bind("127.0.0.1:8080")?
-    .run()
-    .await
+    .shutdown_timeout(settings.server.shutdown_timeout_secs)
+    .disable_signals()
+    .bind(settings.server.bind_address(8080))?
+    .run();
+    stop_server_on_signal(server.handle());
+    let result = server.await;
+
+    // Flush spans that are still buffered before exiting
+    if let Err(e) = tracer_provider.shutdown() {
+        warn!("Failed to shut down tracer provider: {}", e);
+    }
+    result
 }
 
 // use authentication_api::handlers::user::UserHandler;
-// use authentication_api::services::certification::CertificateService;
+// use common::services::certification::CertificateService;
 
 // #[tokio::main]
 // async fn main() {
@@ -0,0 +1,23 @@
+use common::database::migrations::{Migration, SetMissingField};
+use common::models::tenant::DEFAULT_TENANT;
+
+/// Migrations of the Authentication API in the order they run, append new
+/// ones with the next version and never change or remove an applied one
+pub fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(SetMissingField {
+            version: 1,
+            name: "users_default_tenant",
+            collection: "users",
+            field: "tenant_id",
+            value: DEFAULT_TENANT.into(),
+        }),
+        Box::new(SetMissingField {
+            version: 2,
+            name: "users_enabled_by_default",
+            collection: "users",
+            field: "disabled",
+            value: false.into(),
+        }),
+    ]
+}
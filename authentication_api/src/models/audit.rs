@@ -0,0 +1,42 @@
+use common::models::tenant::default_tenant;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Implementation of AuditEvent struct, a record of a security relevant action
+pub struct AuditEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
+    pub action: String,
+    pub actor_id: String,
+    pub actor_email: Option<String>,
+    pub target_id: Option<String>,
+    pub details: Document,
+    pub created_at: DateTime,
+}
+
+/// Implementation of AuditEvent for creating a new event
+impl AuditEvent {
+    pub fn create_new(
+        tenant_id: &str,
+        action: &str,
+        actor_id: &str,
+        actor_email: Option<String>,
+        target_id: Option<String>,
+        details: Document,
+    ) -> Self {
+        AuditEvent {
+            id: Some(ObjectId::new()),
+            tenant_id: tenant_id.to_string(),
+            action: action.to_string(),
+            actor_id: actor_id.to_string(),
+            actor_email,
+            target_id,
+            details,
+            created_at: DateTime::now(),
+        }
+    }
+}
@@ -1,13 +1,17 @@
+use common::models::tenant::default_tenant;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 /// Implementation of Login struct, used for user login
 pub struct Login {
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 /// Implementation of LoginResponse struct, used for user login response
 pub struct LoginResponse {
     pub token: String,
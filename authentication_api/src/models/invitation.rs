@@ -0,0 +1,91 @@
+use common::models::schema::{DateTimeSchema, ObjectIdSchema};
+use common::models::tenant::default_tenant;
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Invitation {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
+    pub email: String,
+    pub scopes: Vec<String>,
+    pub token: String,
+    pub invited_by: String,
+    pub expires_at: DateTime,
+    pub accepted_at: Option<DateTime>,
+    pub created_at: DateTime,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+/// Implementation of NewInvitationRequest struct, used to invite a user
+pub struct NewInvitationRequest {
+    pub email: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Overrides the configured invitation expiry, up to the configured maximum
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+/// Implementation of AcceptInvitationRequest struct, used by the invitee to set a password
+pub struct AcceptInvitationRequest {
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+/// Implementation of InvitationResponse struct, the token is handed to the invitee
+pub struct InvitationResponse {
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    pub tenant_id: String,
+    pub email: String,
+    pub scopes: Vec<String>,
+    pub token: String,
+    #[schema(value_type = DateTimeSchema)]
+    pub expires_at: DateTime,
+}
+
+/// Implementation of Invitation for creating a new invitation
+impl Invitation {
+    pub fn create_new(
+        tenant_id: &str,
+        invited_by: &str,
+        request: NewInvitationRequest,
+        token: String,
+        expires_at: DateTime,
+    ) -> Self {
+        let now = DateTime::now();
+        Invitation {
+            id: Some(ObjectId::new()),
+            tenant_id: tenant_id.to_string(),
+            email: request.email,
+            scopes: request.scopes,
+            token,
+            invited_by: invited_by.to_string(),
+            expires_at,
+            accepted_at: None,
+            created_at: now,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+
+    /// Implementation of InvitationResponse for converting Invitation to InvitationResponse
+    pub fn to_invitation_response(&self) -> InvitationResponse {
+        InvitationResponse {
+            id: self.id,
+            tenant_id: self.tenant_id.clone(),
+            email: self.email.clone(),
+            scopes: self.scopes.clone(),
+            token: self.token.clone(),
+            expires_at: self.expires_at,
+        }
+    }
+}
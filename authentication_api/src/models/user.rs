@@ -1,32 +1,52 @@
+use common::models::schema::{DateTimeSchema, ObjectIdSchema};
+use common::models::tenant::default_tenant;
 use mongodb::bson::DateTime;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
     pub email: String,
     pub password: String,
     pub scopes: Vec<String>,
+    #[serde(default)]
+    pub disabled: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
 
-#[derive(Serialize, Deserialize)]
-/// Implementation of NewUserRequest struct, used for user registration
+#[derive(Serialize, Deserialize, ToSchema)]
+/// Body of the public registration endpoint, registered users join the default tenant
+pub struct RegisterUserRequest {
+    pub email: String,
+    pub password: String,
+}
+
+/// Implementation of NewUserRequest struct, a user to create in a tenant decided by
+/// the server, from registration, an invitation, an import or the admin CLI
 pub struct NewUserRequest {
+    pub tenant_id: String,
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 /// Implementation of UserResponse struct, used for user response
 pub struct UserResponse {
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
+    pub tenant_id: String,
     pub email: String,
     pub scopes: Vec<String>,
+    pub disabled: bool,
+    #[schema(value_type = DateTimeSchema)]
     pub created_at: DateTime,
+    #[schema(value_type = DateTimeSchema)]
     pub updated_at: DateTime,
 }
 
@@ -36,9 +56,11 @@ impl User {
         let now = DateTime::now();
         User {
             id: Some(ObjectId::new()),
+            tenant_id: new_user.tenant_id,
             email: new_user.email,
             password: new_user.password,
             scopes: vec![],
+            disabled: false,
             created_at: now,
             updated_at: now,
         }
@@ -47,9 +69,11 @@ impl User {
     /// Implementation of UserResponse for converting User to UserResponse
     pub fn to_user_response(&self) -> UserResponse {
         UserResponse {
-            id: self.id.clone(),
+            id: self.id,
+            tenant_id: self.tenant_id.clone(),
             email: self.email.clone(),
             scopes: self.scopes.clone(),
+            disabled: self.disabled,
             created_at: self.created_at,
             updated_at: self.updated_at,
         }
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// File formats supported by user import and export
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UserFileFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+/// Implementation of ImportUserRecord struct, one user of an import file
+pub struct ImportUserRecord {
+    pub email: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Argon2 PHC string or legacy bcrypt hash, users without one must
+    /// have a password set before they can log in
+    pub password_hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Created,
+    /// Valid row that would be created, reported by dry runs
+    Valid,
+    /// Email already exists, imports are idempotent by email
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+/// Implementation of ImportRowResult struct, the outcome of one row, rows are numbered from 1
+pub struct ImportRowResult {
+    pub row: usize,
+    pub email: Option<String>,
+    pub status: ImportRowStatus,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+/// Implementation of ImportReport struct, used for user import response
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub valid: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportReport {
+    pub fn new(dry_run: bool, rows: Vec<ImportRowResult>) -> Self {
+        let count = |status| rows.iter().filter(|row| row.status == status).count();
+        ImportReport {
+            dry_run,
+            created: count(ImportRowStatus::Created),
+            valid: count(ImportRowStatus::Valid),
+            skipped: count(ImportRowStatus::Skipped),
+            failed: count(ImportRowStatus::Failed),
+            rows,
+        }
+    }
+}
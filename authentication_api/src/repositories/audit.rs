@@ -0,0 +1,48 @@
+use crate::errors::api_error::ApiError;
+use crate::models::audit::AuditEvent;
+use async_trait::async_trait;
+use common::services::metrics::time_mongo;
+use log::info;
+use mongodb::{Collection, Database};
+
+/// Storage of the audit log, events are only ever appended
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Writes an event to the audit log
+    async fn record(&self, event: AuditEvent) -> Result<(), ApiError>;
+}
+
+pub struct MongoAuditRepository {
+    collection: Collection<AuditEvent>,
+}
+
+/// Initializes the MongoAuditRepository
+/// returns a MongoAuditRepository instance over the service's shared database connection
+impl MongoAuditRepository {
+    pub fn new(database: &Database) -> Self {
+        let collection: Collection<AuditEvent> = database.collection::<AuditEvent>("audit_log");
+        MongoAuditRepository { collection }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MongoAuditRepository {
+    async fn record(&self, event: AuditEvent) -> Result<(), ApiError> {
+        log_event(&event);
+        time_mongo(
+            self.collection.name(),
+            "insert_one",
+            self.collection.insert_one(&event),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Every audit event is also logged, whichever repository stores it
+pub(crate) fn log_event(event: &AuditEvent) {
+    info!(
+        "Audit: {} by {} on {:?}",
+        event.action, event.actor_id, event.target_id
+    );
+}
@@ -0,0 +1,137 @@
+use crate::errors::api_error::ApiError;
+use crate::models::audit::AuditEvent;
+use crate::models::invitation::Invitation;
+use crate::models::user::User;
+use crate::repositories::audit::{AuditRepository, log_event};
+use crate::repositories::invitation::InvitationRepository;
+use crate::repositories::user::{UserRepository, parse_id};
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{DateTime, Document, from_document, to_document};
+use std::sync::Mutex;
+
+/// Users kept in memory, for tests and running without MongoDB
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<Vec<User>>,
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create_user(&self, mut user: User) -> Result<User, ApiError> {
+        let mut users = self.users.lock().unwrap();
+        if users
+            .iter()
+            .any(|existing| existing.tenant_id == user.tenant_id && existing.email == user.email)
+        {
+            return Err(ApiError::Conflict(format!(
+                "User with email {} already exists in tenant {}",
+                user.email, user.tenant_id
+            )));
+        }
+        user.id.get_or_insert_with(ObjectId::new);
+        users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn find_by_email(&self, tenant_id: &str, email: &str) -> Result<Option<User>, ApiError> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .iter()
+            .find(|user| user.tenant_id == tenant_id && user.email == email)
+            .cloned())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, ApiError> {
+        let id = parse_id(id)?;
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|user| user.id == Some(id)).cloned())
+    }
+
+    async fn list_users(&self, tenant_id: Option<&str>) -> Result<Vec<User>, ApiError> {
+        let users = self.users.lock().unwrap();
+        let mut users: Vec<User> = users
+            .iter()
+            .filter(|user| tenant_id.is_none_or(|tenant_id| user.tenant_id == tenant_id))
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| (&a.tenant_id, &a.email).cmp(&(&b.tenant_id, &b.email)));
+        Ok(users)
+    }
+
+    async fn update_user(&self, id: &str, update_doc: Document) -> Result<User, ApiError> {
+        let id = parse_id(id)?;
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .iter_mut()
+            .find(|user| user.id == Some(id))
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+        *user = apply_set(user, update_doc)?;
+        Ok(user.clone())
+    }
+}
+
+/// Invitations kept in memory, for tests and running without MongoDB
+#[derive(Default)]
+pub struct InMemoryInvitationRepository {
+    invitations: Mutex<Vec<Invitation>>,
+}
+
+#[async_trait]
+impl InvitationRepository for InMemoryInvitationRepository {
+    async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation, ApiError> {
+        self.invitations.lock().unwrap().push(invitation.clone());
+        Ok(invitation)
+    }
+
+    async fn find_by_token(&self, token: &str) -> Result<Option<Invitation>, ApiError> {
+        let invitations = self.invitations.lock().unwrap();
+        Ok(invitations
+            .iter()
+            .find(|invitation| invitation.token == token)
+            .cloned())
+    }
+
+    async fn mark_accepted(&self, token: &str) -> Result<bool, ApiError> {
+        let mut invitations = self.invitations.lock().unwrap();
+        match invitations
+            .iter_mut()
+            .find(|invitation| invitation.token == token && invitation.accepted_at.is_none())
+        {
+            Some(invitation) => {
+                invitation.accepted_at = Some(DateTime::now());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Audit log kept in memory, tests can inspect the recorded events
+#[derive(Default)]
+pub struct InMemoryAuditRepository {
+    events: Mutex<Vec<AuditEvent>>,
+}
+
+impl InMemoryAuditRepository {
+    /// The recorded events, oldest first
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn record(&self, event: AuditEvent) -> Result<(), ApiError> {
+        log_event(&event);
+        self.events.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+/// Applies a `$set` of top level fields the way MongoDB would, by round tripping through BSON
+fn apply_set(user: &User, update_doc: Document) -> Result<User, ApiError> {
+    let mut document = to_document(user)?;
+    document.extend(update_doc);
+    from_document(document).map_err(|e| ApiError::Internal(format!("Deserialization error: {}", e)))
+}
@@ -0,0 +1,74 @@
+use crate::errors::api_error::ApiError;
+use crate::models::invitation::Invitation;
+use async_trait::async_trait;
+use common::database::indexes::IndexSpec;
+use common::services::metrics::time_mongo;
+use mongodb::bson::{DateTime, doc};
+use mongodb::{Collection, Database};
+
+/// Storage of invitations
+#[async_trait]
+pub trait InvitationRepository: Send + Sync {
+    /// Stores a new invitation
+    async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation, ApiError>;
+
+    async fn find_by_token(&self, token: &str) -> Result<Option<Invitation>, ApiError>;
+
+    /// Marks a pending invitation as accepted, returns false when it was
+    /// already accepted so an invitation can only be used once.
+    async fn mark_accepted(&self, token: &str) -> Result<bool, ApiError>;
+}
+
+pub struct MongoInvitationRepository {
+    collection: Collection<Invitation>,
+}
+
+/// Initializes the MongoInvitationRepository
+/// returns a MongoInvitationRepository instance over the service's shared database connection
+impl MongoInvitationRepository {
+    pub fn new(database: &Database) -> Self {
+        let collection: Collection<Invitation> = database.collection::<Invitation>("invitations");
+        MongoInvitationRepository { collection }
+    }
+
+    /// Invitations are looked up by token when accepted, a token is only issued once
+    pub fn indexes() -> Vec<IndexSpec> {
+        vec![IndexSpec::ascending("invitations", "token", &["token"]).unique()]
+    }
+}
+
+#[async_trait]
+impl InvitationRepository for MongoInvitationRepository {
+    async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation, ApiError> {
+        time_mongo(
+            self.collection.name(),
+            "insert_one",
+            self.collection.insert_one(&invitation),
+        )
+        .await?;
+        Ok(invitation)
+    }
+
+    async fn find_by_token(&self, token: &str) -> Result<Option<Invitation>, ApiError> {
+        let filter = doc! {"token": token};
+        let invitation = time_mongo(
+            self.collection.name(),
+            "find_one",
+            self.collection.find_one(filter),
+        )
+        .await?;
+        Ok(invitation)
+    }
+
+    async fn mark_accepted(&self, token: &str) -> Result<bool, ApiError> {
+        let filter = doc! {"token": token, "accepted_at": null};
+        let update = doc! {"$set": {"accepted_at": DateTime::now()}};
+        let update_result = time_mongo(
+            self.collection.name(),
+            "update_one",
+            self.collection.update_one(filter, update),
+        )
+        .await?;
+        Ok(update_result.modified_count == 1)
+    }
+}
@@ -0,0 +1,160 @@
+use crate::errors::api_error::ApiError;
+use crate::models;
+use async_trait::async_trait;
+use common::database::indexes::IndexSpec;
+use common::database::mongo_db::is_duplicate_key;
+use common::models::tenant::tenant_filter;
+use common::services::metrics::time_mongo;
+use futures::TryStreamExt;
+use models::user::User;
+use mongodb::bson::{Document, doc, oid::ObjectId};
+use mongodb::{Collection, Database};
+
+/// Storage of users, handlers only depend on this trait so the
+/// in-memory implementation can replace MongoDB in tests
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Creates a new user, emails are unique within a tenant
+    async fn create_user(&self, user: User) -> Result<User, ApiError>;
+
+    async fn find_by_email(&self, tenant_id: &str, email: &str) -> Result<Option<User>, ApiError>;
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, ApiError>;
+
+    /// Lists users ordered by tenant and email, optionally limited to one tenant
+    async fn list_users(&self, tenant_id: Option<&str>) -> Result<Vec<User>, ApiError>;
+
+    /// Sets the fields of `update_doc` and returns the updated user,
+    /// unknown ids are reported as not found
+    async fn update_user(&self, id: &str, update_doc: Document) -> Result<User, ApiError>;
+}
+
+pub struct MongoUserRepository {
+    collection: Collection<User>,
+}
+
+/// Initializes the MongoUserRepository
+/// returns a MongoUserRepository instance over the service's shared database connection
+impl MongoUserRepository {
+    pub fn new(database: &Database) -> Self {
+        let collection: Collection<User> = database.collection::<User>("users");
+        MongoUserRepository { collection }
+    }
+
+    /// Emails are unique within a tenant, the index also serves login lookups
+    pub fn indexes() -> Vec<IndexSpec> {
+        vec![IndexSpec::ascending("users", "tenant_id_email", &["tenant_id", "email"]).unique()]
+    }
+}
+
+#[async_trait]
+impl UserRepository for MongoUserRepository {
+    async fn create_user(&self, user: User) -> Result<User, ApiError> {
+        if self
+            .find_by_email(&user.tenant_id, &user.email)
+            .await?
+            .is_some()
+        {
+            return Err(ApiError::Conflict(format!(
+                "User with email {} already exists in tenant {}",
+                user.email, user.tenant_id
+            )));
+        }
+        // The unique index catches a concurrent create slipping past the check above
+        let insert_result = time_mongo(
+            self.collection.name(),
+            "insert_one",
+            self.collection.insert_one(&user),
+        )
+        .await
+        .map_err(|e| {
+            if is_duplicate_key(&e) {
+                ApiError::Conflict(format!(
+                    "User with email {} already exists in tenant {}",
+                    user.email, user.tenant_id
+                ))
+            } else {
+                e.into()
+            }
+        })?;
+        if let Some(inserted_id) = insert_result.inserted_id.as_object_id() {
+            let filter = doc! { "_id": inserted_id };
+            if let Some(created_user) = time_mongo(
+                self.collection.name(),
+                "find_one",
+                self.collection.find_one(filter),
+            )
+            .await?
+            {
+                return Ok(created_user);
+            }
+        }
+        Err(ApiError::Internal("Failed to create user".to_string()))
+    }
+
+    async fn find_by_email(&self, tenant_id: &str, email: &str) -> Result<Option<User>, ApiError> {
+        let filter = doc! {"tenant_id": tenant_filter(tenant_id), "email": email};
+        let user = time_mongo(
+            self.collection.name(),
+            "find_one",
+            self.collection.find_one(filter),
+        )
+        .await?;
+        Ok(user)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, ApiError> {
+        let filter = doc! {"_id": parse_id(id)?};
+        let user = time_mongo(
+            self.collection.name(),
+            "find_one",
+            self.collection.find_one(filter),
+        )
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn list_users(&self, tenant_id: Option<&str>) -> Result<Vec<User>, ApiError> {
+        let filter = match tenant_id {
+            Some(tenant_id) => doc! {"tenant_id": tenant_filter(tenant_id)},
+            None => doc! {},
+        };
+        let cursor = time_mongo(
+            self.collection.name(),
+            "find",
+            self.collection
+                .find(filter)
+                .sort(doc! {"tenant_id": 1, "email": 1}),
+        )
+        .await?;
+        let users: Vec<User> = cursor.try_collect().await?;
+        Ok(users)
+    }
+
+    async fn update_user(&self, id: &str, update_doc: Document) -> Result<User, ApiError> {
+        let filter = doc! { "_id": parse_id(id)? };
+        let update = doc! { "$set": update_doc };
+
+        let update_result = time_mongo(
+            self.collection.name(),
+            "update_one",
+            self.collection.update_one(filter.clone(), update),
+        )
+        .await?;
+        if update_result.matched_count == 0 {
+            return Err(ApiError::NotFound("User not found".to_string()));
+        }
+        time_mongo(
+            self.collection.name(),
+            "find_one",
+            self.collection.find_one(filter),
+        )
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found after update".to_string()))
+    }
+}
+
+pub(crate) fn parse_id(id: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id).map_err(|_| ApiError::Validation(format!("Invalid user id {}", id)))
+}
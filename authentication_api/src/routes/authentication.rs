@@ -1,28 +1,67 @@
-use crate::handlers::authentication::AuthenticationHandler;
-use crate::models::authentication::Login;
+use crate::errors::api_error::ApiError;
+use crate::handlers::authentication::{AuthenticationHandler, IMPERSONATE_SCOPE};
+use crate::models::authentication::{Login, LoginResponse};
 use actix_web::{HttpResponse, Responder, post, web};
+use common::errors::problem::ProblemDetails;
+use common::middleware::authentication::{AuthenticatedUser, RequireScope};
 
+#[utoipa::path(
+    tag = "authentication",
+    request_body = Login,
+    responses(
+        (status = 200, description = "Token issued", body = LoginResponse),
+        (status = 401, description = "Invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Account disabled", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 #[post("/auth/login")]
 /// Logs in a user and returns a JWT token.
 async fn login(
     login_data: web::Json<Login>,
     handler: web::Data<AuthenticationHandler>,
-) -> impl Responder {
-    match handler.login_user(login_data.into_inner()).await {
-        Ok(response) => HttpResponse::Ok().json(response),
-        Err(e) => HttpResponse::Unauthorized().body(e),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let response = handler.login_user(login_data.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
 }
 
+#[utoipa::path(
+    tag = "authentication",
+    request_body(content = String, description = "The JWT to verify"),
+    responses(
+        (status = 200, description = "Whether the token is valid", body = bool),
+        (status = 401, description = "Invalid token", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 #[post("/auth/verify")]
 /// Verifies a JWT token is valid, would be sent in the header.
 async fn verify_token(
     token: web::Json<String>,
     handler: web::Data<AuthenticationHandler>,
-) -> impl Responder {
-    let token_str = token.as_str();
-    match handler.verify_token(token_str).await {
-        Ok(response) => HttpResponse::Ok().json(response),
-        Err(e) => HttpResponse::Unauthorized().body(e),
-    }
+) -> Result<HttpResponse, ApiError> {
+    let response = handler.verify_token(token.as_str()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    tag = "authentication",
+    params(("id" = String, Path, description = "Id of the user to impersonate")),
+    responses(
+        (status = 200, description = "Short lived token for the user", body = LoginResponse),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope, user disabled or holding scopes the caller does not hold", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["user:impersonate"]))
+)]
+#[post("/auth/impersonate/{id}", wrap = "RequireScope(IMPERSONATE_SCOPE)")]
+/// Issues a short lived token for another user of the same tenant,
+/// requires "user:impersonate" scope and is written to the audit log.
+/// Only users whose scopes the caller also holds can be impersonated.
+async fn impersonate(
+    handler: web::Data<AuthenticationHandler>,
+    id: web::Path<String>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let response = handler.impersonate_user(&user, id.as_str()).await?;
+    Ok(HttpResponse::Ok().json(response))
 }
@@ -0,0 +1,67 @@
+use crate::errors::api_error::ApiError;
+use crate::handlers::invitation::InvitationHandler;
+use crate::models::invitation::{
+    AcceptInvitationRequest, InvitationResponse, NewInvitationRequest,
+};
+use crate::models::user::UserResponse;
+use actix_web::{HttpResponse, Responder, post, web};
+use common::errors::problem::ProblemDetails;
+use common::middleware::authentication::{AuthenticatedUser, RequireScope};
+
+#[utoipa::path(
+    tag = "invitation",
+    request_body = NewInvitationRequest,
+    responses(
+        (status = 200, description = "Invitation created", body = InvitationResponse),
+        (status = 400, description = "Invalid expiry", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope or scope not held by inviter", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "User already exists", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["user:invite"]))
+)]
+#[post("/user/invite", wrap = "RequireScope(\"user:invite\")")]
+/// Invite a user by email, requires "user:invite" scope.
+/// Only scopes held by the inviter can be granted.
+async fn invite_user(
+    invitation_handler: web::Data<InvitationHandler>,
+    invitation: web::Json<NewInvitationRequest>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(scope) = invitation
+        .scopes
+        .iter()
+        .find(|scope| !user.scopes.contains(scope))
+    {
+        return Err(ApiError::Forbidden(format!(
+            "Cannot grant scope {} not held by inviter",
+            scope
+        )));
+    }
+    let handler_response = invitation_handler
+        .invite_user(&user.tenant_id, &user.sub, invitation.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(handler_response))
+}
+
+#[utoipa::path(
+    tag = "invitation",
+    request_body = AcceptInvitationRequest,
+    responses(
+        (status = 200, description = "Invited user created", body = UserResponse),
+        (status = 400, description = "Invitation expired", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Invitation not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Invitation already accepted", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+#[post("/user/invite/accept")]
+/// Accept an invitation and set a password, creates the invited user
+async fn accept_invitation(
+    invitation_handler: web::Data<InvitationHandler>,
+    accept_request: web::Json<AcceptInvitationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let handler_response = invitation_handler
+        .accept_invitation(accept_request.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(handler_response))
+}
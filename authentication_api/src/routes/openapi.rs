@@ -0,0 +1,29 @@
+use crate::routes::{authentication, invitation, user};
+use common::routes::{health, metrics};
+use common::utils::openapi::BearerSecurity;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "authentication_api",
+        description = "Issues and verifies JWTs and manages users and invitations"
+    ),
+    paths(
+        authentication::login,
+        authentication::verify_token,
+        authentication::impersonate,
+        user::register_user,
+        user::get_user,
+        user::import_users,
+        user::export_users,
+        invitation::invite_user,
+        invitation::accept_invitation,
+        health::live,
+        health::ready,
+        metrics::metrics,
+    ),
+    modifiers(&BearerSecurity)
+)]
+/// OpenAPI document of the authentication_api routes
+pub struct ApiDoc;
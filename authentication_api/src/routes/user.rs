@@ -1,36 +1,144 @@
-use crate::models::user::NewUserRequest;
-use crate::{handlers::user::UserHandler, services::certification::CertificateService};
-use actix_web::{Error, HttpResponse, Responder, get, post, web};
-use actix_web_httpauth::extractors::bearer::BearerAuth;
+use crate::errors::api_error::ApiError;
+use crate::handlers::user::UserHandler;
+use crate::models::user::{NewUserRequest, RegisterUserRequest, UserResponse};
+use crate::models::user_import::{ImportReport, UserFileFormat};
+use crate::utils::user_import::{parse_user_import, write_user_export};
+use actix_web::{HttpResponse, Responder, get, post, web};
+use common::errors::problem::ProblemDetails;
+use common::middleware::authentication::{AuthenticatedUser, RequireScope};
+use common::models::tenant::DEFAULT_TENANT;
+use common::utils::load_settings::RegistrationSettings;
+use serde::Deserialize;
+use utoipa::IntoParams;
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+/// Query parameters of the user import endpoint
+pub struct ImportQuery {
+    pub format: UserFileFormat,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+/// Query parameters of the user export endpoint
+pub struct ExportQuery {
+    pub format: Option<UserFileFormat>,
+}
+
+#[utoipa::path(
+    tag = "user",
+    request_body = RegisterUserRequest,
+    responses(
+        (status = 200, description = "User registered", body = UserResponse),
+        (status = 403, description = "Open registration is disabled", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "User already exists", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 #[post("/user/register")]
-/// Register a new user,stuff
+/// Register a new user in the default tenant, unavailable when open registration is disabled
 async fn register_user(
     user_handler: web::Data<UserHandler>,
-    new_user: web::Json<NewUserRequest>,
-) -> impl Responder {
-    match user_handler.create_user(new_user.into_inner()).await {
-        Ok(handler_response) => HttpResponse::Ok().json(handler_response),
-        Err(err_msg) => HttpResponse::InternalServerError().body(err_msg),
+    registration: web::Data<RegistrationSettings>,
+    new_user: web::Json<RegisterUserRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if !registration.open_registration {
+        return Err(ApiError::Forbidden(
+            "Open registration is disabled".to_string(),
+        ));
     }
+    let new_user = new_user.into_inner();
+    let handler_response = user_handler
+        .create_user(NewUserRequest {
+            tenant_id: DEFAULT_TENANT.to_string(),
+            email: new_user.email,
+            password: new_user.password,
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(handler_response))
 }
-#[get("/user/{id}")]
+#[utoipa::path(
+    tag = "user",
+    params(("id" = String, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User found", body = UserResponse),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["user:read"]))
+)]
+#[get("/user/{id}", wrap = "RequireScope(\"user:read\")")]
 /// Get a user by ID, requires "user:read" scope
 async fn get_user(
     user_handler: web::Data<UserHandler>,
-    cert_handler: web::Data<CertificateService>,
     id: web::Path<String>,
-    auth: BearerAuth,
-) -> Result<HttpResponse, Error> {
-    let token = auth.token();
-    let id_ref = id.as_str();
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let handler_response = user_handler.find_user_by_id(id.as_str()).await?;
 
-    // Validate token scope and token
-    match cert_handler.has_scope(token, "user:read") {
-        Ok(()) => match user_handler.find_user_by_id(id_ref).await {
-            Ok(handler_response) => Ok(HttpResponse::Ok().json(handler_response)),
-            Err(err_msg) => Ok(HttpResponse::InternalServerError().body(err_msg)),
-        },
-        Err(resp) => Ok(resp),
+    // Users are only visible within the caller's tenant
+    if handler_response.tenant_id != user.tenant_id {
+        return Err(ApiError::NotFound("User not found".to_string()));
     }
+    Ok(HttpResponse::Ok().json(handler_response))
+}
+
+#[utoipa::path(
+    tag = "user",
+    params(ImportQuery),
+    request_body(content((String = "text/csv"), (String = "application/jsonl")), description = "Users to import, one per row"),
+    responses(
+        (status = 200, description = "Outcome of every row", body = ImportReport),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["user:import"]))
+)]
+#[post("/user/import", wrap = "RequireScope(\"user:import\")")]
+/// Import users into the caller's tenant from CSV or JSON Lines, requires "user:import" scope.
+/// Use `?dry_run=true` to validate the file without creating users.
+/// Rows may only grant scopes held by the caller.
+async fn import_users(
+    user_handler: web::Data<UserHandler>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let records = parse_user_import(query.format, &body);
+    let report = user_handler
+        .import_users(&user.tenant_id, Some(&user), records, query.dry_run)
+        .await;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    tag = "user",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Users of the tenant, JSON Lines unless CSV is requested",
+            content((String = "application/jsonl"), (String = "text/csv"))),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["user:read"]))
+)]
+#[get("/user/export", wrap = "RequireScope(\"user:read\")")]
+/// Export the users of the caller's tenant without password hashes, requires "user:read" scope
+async fn export_users(
+    user_handler: web::Data<UserHandler>,
+    query: web::Query<ExportQuery>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let format = query.format.unwrap_or(UserFileFormat::Jsonl);
+
+    let users = user_handler.list_users(Some(&user.tenant_id)).await?;
+    let body = write_user_export(format, &users)
+        .map_err(|e| ApiError::Internal(format!("Export error: {}", e)))?;
+    let content_type = match format {
+        UserFileFormat::Csv => "text/csv",
+        UserFileFormat::Jsonl => "application/jsonl",
+    };
+    Ok(HttpResponse::Ok().content_type(content_type).body(body))
 }
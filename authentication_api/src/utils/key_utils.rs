@@ -0,0 +1,61 @@
+use argon2::password_hash::rand_core::OsRng;
+use jsonwebtoken::Algorithm;
+use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use std::fs;
+use std::path::Path;
+
+/// Generates a new key pair for the algorithm and writes it as PKCS#8 PEM
+/// files, in the format expected by CertificateService. `bits` is only used
+/// for RSA keys.
+pub fn generate_key_pair(
+    algorithm: Algorithm,
+    private_key_path: &Path,
+    public_key_path: &Path,
+    bits: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (private_pem, public_pem) = match algorithm {
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {
+            let private_key = RsaPrivateKey::new(&mut OsRng, bits)?;
+            let public_key = RsaPublicKey::from(&private_key);
+            (
+                private_key.to_pkcs8_pem(LineEnding::LF)?,
+                public_key.to_public_key_pem(LineEnding::LF)?,
+            )
+        }
+        Algorithm::ES256 => {
+            let private_key = p256::SecretKey::random(&mut OsRng);
+            (
+                private_key.to_pkcs8_pem(LineEnding::LF)?,
+                private_key.public_key().to_public_key_pem(LineEnding::LF)?,
+            )
+        }
+        Algorithm::EdDSA => {
+            let private_key = ed25519_dalek::SigningKey::generate(&mut OsRng);
+            (
+                private_key.to_pkcs8_pem(LineEnding::LF)?,
+                private_key
+                    .verifying_key()
+                    .to_public_key_pem(LineEnding::LF)?,
+            )
+        }
+        _ => return Err(format!("Unsupported key algorithm {:?}", algorithm).into()),
+    };
+
+    for path in [private_key_path, public_key_path] {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(private_key_path, private_pem.as_bytes())?;
+    fs::write(public_key_path, public_pem.as_bytes())?;
+
+    // Only the owner should be able to read the signing key
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(private_key_path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
@@ -1,30 +0,0 @@
-use argon2::{
-    Argon2,
-    password_hash::{
-        Error as PasswordHashError, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
-        rand_core::OsRng,
-    },
-};
-
-#[allow(dead_code)]
-/// Hashes a password using Argon2.
-pub fn hash_password(password: &str) -> Result<String, PasswordHashError> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let hash = argon2
-        .hash_password(password.as_bytes(), &salt)?
-        .to_string();
-    Ok(hash)
-}
-
-#[allow(dead_code)]
-/// Verifies a password against a hash using Argon2.
-pub fn verify_password(password: &str, hash: &str) -> Result<bool, PasswordHashError> {
-    let parsed_hash = PasswordHash::new(hash)?;
-    let argon2 = Argon2::default();
-    match argon2.verify_password(password.as_bytes(), &parsed_hash) {
-        Ok(()) => Ok(true),
-        Err(PasswordHashError::Password) => Ok(false),
-        Err(e) => Err(e),
-    }
-}
@@ -0,0 +1,83 @@
+use crate::models::user::UserResponse;
+use crate::models::user_import::{ImportUserRecord, UserFileFormat};
+use serde::Deserialize;
+
+/// CSV row layout: `email,scopes,password_hash`, scopes are separated by spaces
+#[derive(Deserialize)]
+struct CsvUserRow {
+    email: String,
+    #[serde(default)]
+    scopes: Option<String>,
+    #[serde(default)]
+    password_hash: Option<String>,
+}
+
+/// Parses an import file, every row yields a record or the reason it could not be read
+pub fn parse_user_import(
+    format: UserFileFormat,
+    data: &[u8],
+) -> Vec<Result<ImportUserRecord, String>> {
+    match format {
+        UserFileFormat::Csv => csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(data)
+            .deserialize::<CsvUserRow>()
+            .map(|row| {
+                row.map(|row| ImportUserRecord {
+                    email: row.email,
+                    scopes: row
+                        .scopes
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect(),
+                    password_hash: row.password_hash.filter(|hash| !hash.is_empty()),
+                })
+                .map_err(|e| format!("Invalid CSV row: {}", e))
+            })
+            .collect(),
+        UserFileFormat::Jsonl => String::from_utf8_lossy(data)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<ImportUserRecord>(line)
+                    .map_err(|e| format!("Invalid JSON line: {}", e))
+            })
+            .collect(),
+    }
+}
+
+/// Writes users as an export file, password hashes are never included
+pub fn write_user_export(
+    format: UserFileFormat,
+    users: &[UserResponse],
+) -> Result<Vec<u8>, String> {
+    match format {
+        UserFileFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer
+                .write_record(["id", "email", "scopes", "disabled", "created_at"])
+                .map_err(|e| e.to_string())?;
+            for user in users {
+                writer
+                    .write_record([
+                        user.id.map(|id| id.to_hex()).unwrap_or_default(),
+                        user.email.clone(),
+                        user.scopes.join(" "),
+                        user.disabled.to_string(),
+                        user.created_at.try_to_rfc3339_string().unwrap_or_default(),
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+            writer.into_inner().map_err(|e| e.to_string())
+        }
+        UserFileFormat::Jsonl => {
+            let mut output = Vec::new();
+            for user in users {
+                serde_json::to_writer(&mut output, user).map_err(|e| e.to_string())?;
+                output.push(b'\n');
+            }
+            Ok(output)
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use actix_web::{App, test, web};
+use authentication_api::app::configure;
+use authentication_api::handlers::authentication::AuthenticationHandler;
+use authentication_api::handlers::invitation::InvitationHandler;
+use authentication_api::handlers::user::UserHandler;
+use authentication_api::models::user::{NewUserRequest, User};
+use authentication_api::repositories::in_memory::{
+    InMemoryAuditRepository, InMemoryInvitationRepository, InMemoryUserRepository,
+};
+use authentication_api::repositories::invitation::InvitationRepository;
+use authentication_api::repositories::user::UserRepository;
+use common::services::certification::CertificateService;
+use common::utils::load_settings::{PasswordSettings, RegistrationSettings};
+use common::utils::password_utils::hash_password;
+use jsonwebtoken::Algorithm;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+/// In-memory storage shared by the handlers, kept so tests can seed and inspect it
+#[derive(Default, Clone)]
+struct Backends {
+    users: Arc<InMemoryUserRepository>,
+    invitations: Arc<InMemoryInvitationRepository>,
+    audit: Arc<InMemoryAuditRepository>,
+}
+
+fn certificate_service() -> CertificateService {
+    let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    CertificateService::with_signing_key(
+        &format!("{}/private_key.pem", fixtures),
+        &format!("{}/public_key.pem", fixtures),
+        Algorithm::EdDSA,
+    )
+    .expect("Failed to load test keys")
+}
+
+/// Minimal Argon2 cost so tests do not spend their time hashing
+fn password_settings() -> PasswordSettings {
+    PasswordSettings {
+        memory_kib: 8,
+        iterations: 1,
+        parallelism: 1,
+    }
+}
+
+fn app(
+    backends: Backends,
+    registration: RegistrationSettings,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let auth_handler = AuthenticationHandler::new(
+        backends.users.clone(),
+        backends.audit.clone(),
+        certificate_service(),
+        password_settings(),
+    );
+    let user_handler = UserHandler::new(backends.users.clone(), password_settings());
+    let invitation_handler = InvitationHandler::new(
+        backends.invitations.clone(),
+        backends.users.clone(),
+        &registration,
+        password_settings(),
+    );
+    App::new()
+        .app_data(web::Data::new(auth_handler))
+        .app_data(web::Data::new(user_handler))
+        .app_data(web::Data::new(invitation_handler))
+        .app_data(web::Data::new(certificate_service()))
+        .app_data(web::Data::new(registration))
+        .configure(configure)
+}
+
+async fn seed_user(backends: &Backends, tenant_id: &str, email: &str, scopes: &[&str]) -> String {
+    let mut user = User::create_new(NewUserRequest {
+        tenant_id: tenant_id.to_string(),
+        email: email.to_string(),
+        password: hash_password(PASSWORD, &password_settings()).unwrap(),
+    });
+    user.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+    let user = backends.users.create_user(user).await.unwrap();
+    user.id.unwrap().to_hex()
+}
+
+fn login_request(tenant_id: &str, email: &str, password: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({"tenant_id": tenant_id, "email": email, "password": password}))
+}
+
+fn bearer(token: &str) -> (actix_web::http::header::HeaderName, String) {
+    (AUTHORIZATION, format!("Bearer {}", token))
+}
+
+#[actix_web::test]
+async fn register_then_login_issues_a_valid_token() {
+    let backends = Backends::default();
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = test::TestRequest::post()
+        .uri("/user/register")
+        .set_json(json!({"email": "ada@example.com", "password": PASSWORD, "tenant_id": "acme"}))
+        .to_request();
+    let user: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(user["email"], "ada@example.com");
+    // The tenant is not taken from the public registration body
+    assert_eq!(user["tenant_id"], "default");
+    assert!(user.get("password").is_none());
+
+    let request = login_request("default", "ada@example.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+    let token = login["token"].as_str().unwrap();
+
+    let request = test::TestRequest::post()
+        .uri("/auth/verify")
+        .set_json(token)
+        .to_request();
+    let valid: bool = test::call_and_read_body_json(&app, request).await;
+    assert!(valid);
+}
+
+#[actix_web::test]
+async fn failures_are_problem_details() {
+    let backends = Backends::default();
+    seed_user(&backends, "default", "ada@example.com", &[]).await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("default", "ada@example.com", "wrong password").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/problem+json"
+    );
+    let problem: Value = test::read_body_json(response).await;
+    assert_eq!(problem["code"], "unauthorized");
+
+    let request = test::TestRequest::post()
+        .uri("/user/register")
+        .set_json(json!({"email": "ada@example.com", "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = test::TestRequest::post()
+        .uri("/auth/login")
+        .insert_header((CONTENT_TYPE, "application/json"))
+        .set_payload("{\"email\": ")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/problem+json"
+    );
+    let problem: Value = test::read_body_json(response).await;
+    assert_eq!(problem["code"], "validation_failed");
+}
+
+#[actix_web::test]
+async fn disabled_accounts_are_only_reported_after_the_password() {
+    let backends = Backends::default();
+    let id = seed_user(&backends, "default", "ada@example.com", &[]).await;
+    UserHandler::new(backends.users.clone(), password_settings())
+        .set_user_disabled(&id, true)
+        .await
+        .unwrap();
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("default", "ada@example.com", "wrong password").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let problem: Value = test::read_body_json(response).await;
+    assert_eq!(problem["detail"], "Invalid credentials");
+
+    let request = login_request("default", "ada@example.com", PASSWORD).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn registration_can_be_disabled() {
+    let backends = Backends::default();
+    let registration = RegistrationSettings {
+        open_registration: false,
+        ..RegistrationSettings::default()
+    };
+    let app = test::init_service(app(backends.clone(), registration)).await;
+
+    let request = test::TestRequest::post()
+        .uri("/user/register")
+        .set_json(json!({"email": "ada@example.com", "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn get_user_requires_scope_and_same_tenant() {
+    let backends = Backends::default();
+    seed_user(&backends, "acme", "reader@acme.com", &["user:read"]).await;
+    seed_user(&backends, "acme", "plain@acme.com", &[]).await;
+    let colleague = seed_user(&backends, "acme", "colleague@acme.com", &[]).await;
+    let outsider = seed_user(&backends, "globex", "outsider@globex.com", &[]).await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("acme", "plain@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+    let request = test::TestRequest::get()
+        .uri(&format!("/user/{}", colleague))
+        .insert_header(bearer(login["token"].as_str().unwrap()))
+        .to_request();
+    // RequireScope fails the request with an error the server renders as the response
+    let error = test::try_call_service(&app, request)
+        .await
+        .err()
+        .expect("Missing scope should be refused");
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::FORBIDDEN
+    );
+
+    let request = login_request("acme", "reader@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+    let token = login["token"].as_str().unwrap();
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/user/{}", colleague))
+        .insert_header(bearer(token))
+        .to_request();
+    let user: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(user["email"], "colleague@acme.com");
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/user/{}", outsider))
+        .insert_header(bearer(token))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn invitation_creates_user_with_invited_scopes_once() {
+    let backends = Backends::default();
+    seed_user(
+        &backends,
+        "acme",
+        "admin@acme.com",
+        &["user:invite", "user:read"],
+    )
+    .await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("acme", "admin@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+    let token = login["token"].as_str().unwrap();
+
+    let request = test::TestRequest::post()
+        .uri("/user/invite")
+        .insert_header(bearer(token))
+        .set_json(json!({"email": "new@acme.com", "scopes": ["customer:manager"]}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    for expires_in_hours in [0, 721, i64::MAX] {
+        let request = test::TestRequest::post()
+            .uri("/user/invite")
+            .insert_header(bearer(token))
+            .set_json(json!({
+                "email": "new@acme.com",
+                "scopes": ["user:read"],
+                "expires_in_hours": expires_in_hours
+            }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let request = test::TestRequest::post()
+        .uri("/user/invite")
+        .insert_header(bearer(token))
+        .set_json(json!({"email": "new@acme.com", "scopes": ["user:read"]}))
+        .to_request();
+    let invitation: Value = test::call_and_read_body_json(&app, request).await;
+
+    let accept = json!({"token": invitation["token"], "password": PASSWORD});
+    let request = test::TestRequest::post()
+        .uri("/user/invite/accept")
+        .set_json(&accept)
+        .to_request();
+    let user: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(user["tenant_id"], "acme");
+    assert_eq!(user["scopes"], json!(["user:read"]));
+
+    let request = test::TestRequest::post()
+        .uri("/user/invite/accept")
+        .set_json(&accept)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let request = login_request("acme", "new@acme.com", PASSWORD).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn failed_acceptance_leaves_the_invitation_usable() {
+    let backends = Backends::default();
+    seed_user(
+        &backends,
+        "acme",
+        "admin@acme.com",
+        &["user:invite", "user:read"],
+    )
+    .await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("acme", "admin@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+    let request = test::TestRequest::post()
+        .uri("/user/invite")
+        .insert_header(bearer(login["token"].as_str().unwrap()))
+        .set_json(json!({"email": "new@acme.com", "scopes": ["user:read"]}))
+        .to_request();
+    let invitation: Value = test::call_and_read_body_json(&app, request).await;
+    let token = invitation["token"].as_str().unwrap();
+
+    // The user appears between inviting and accepting, creating it fails
+    seed_user(&backends, "acme", "new@acme.com", &[]).await;
+    let request = test::TestRequest::post()
+        .uri("/user/invite/accept")
+        .set_json(json!({"token": token, "password": PASSWORD}))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let stored = backends
+        .invitations
+        .find_by_token(token)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(stored.accepted_at.is_none());
+}
+
+#[actix_web::test]
+async fn impersonation_is_audited() {
+    let backends = Backends::default();
+    seed_user(
+        &backends,
+        "acme",
+        "admin@acme.com",
+        &["user:impersonate", "user:read"],
+    )
+    .await;
+    let target = seed_user(&backends, "acme", "target@acme.com", &["user:read"]).await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("acme", "admin@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/auth/impersonate/{}", target))
+        .insert_header(bearer(login["token"].as_str().unwrap()))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = backends.audit.events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].action, "user.impersonate");
+    assert_eq!(events[0].target_id.as_deref(), Some(target.as_str()));
+}
+
+#[actix_web::test]
+async fn more_privileged_users_cannot_be_impersonated() {
+    let backends = Backends::default();
+    seed_user(
+        &backends,
+        "acme",
+        "admin@acme.com",
+        &["user:impersonate", "user:read"],
+    )
+    .await;
+    let target = seed_user(
+        &backends,
+        "acme",
+        "owner@acme.com",
+        &["user:read", "user:admin"],
+    )
+    .await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("acme", "admin@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/auth/impersonate/{}", target))
+        .insert_header(bearer(login["token"].as_str().unwrap()))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(backends.audit.events().is_empty());
+}
+
+#[actix_web::test]
+async fn import_is_idempotent_and_export_lists_the_tenant() {
+    let backends = Backends::default();
+    seed_user(
+        &backends,
+        "acme",
+        "admin@acme.com",
+        &["user:import", "user:read"],
+    )
+    .await;
+    seed_user(&backends, "globex", "outsider@globex.com", &[]).await;
+    let app = test::init_service(app(backends.clone(), RegistrationSettings::default())).await;
+
+    let request = login_request("acme", "admin@acme.com", PASSWORD).to_request();
+    let login: Value = test::call_and_read_body_json(&app, request).await;
+    let token = login["token"].as_str().unwrap();
+
+    let file = "{\"email\":\"one@acme.com\",\"scopes\":[\"user:read\"]}\n\
+                {\"email\":\"not an email\"}\n\
+                {\"email\":\"root@acme.com\",\"scopes\":[\"user:admin\"]}\n\
+                {\"email\":\"admin@acme.com\"}\n";
+    let import = |dry_run: bool| {
+        test::TestRequest::post()
+            .uri(&format!("/user/import?format=jsonl&dry_run={}", dry_run))
+            .insert_header(bearer(token))
+            .set_payload(file)
+            .to_request()
+    };
+
+    let report: Value = test::call_and_read_body_json(&app, import(true)).await;
+    assert_eq!(report["valid"], 1);
+    assert_eq!(report["failed"], 2);
+    assert_eq!(report["skipped"], 1);
+    // Scopes the importer does not hold cannot be granted
+    assert_eq!(
+        report["rows"][2]["message"],
+        "Cannot grant scope user:admin not held by importer"
+    );
+
+    let report: Value = test::call_and_read_body_json(&app, import(false)).await;
+    assert_eq!(report["created"], 1);
+
+    // Imported without a password hash, the user cannot log in until one is set
+    for password in ["", PASSWORD] {
+        let request = login_request("acme", "one@acme.com", password).to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    let report: Value = test::call_and_read_body_json(&app, import(false)).await;
+    assert_eq!(report["created"], 0);
+    assert_eq!(report["skipped"], 2);
+
+    let request = test::TestRequest::get()
+        .uri("/user/export")
+        .insert_header(bearer(token))
+        .to_request();
+    let body = test::call_and_read_body(&app, request).await;
+    let emails: Vec<String> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["email"].to_string())
+        .collect();
+    assert_eq!(emails, ["\"admin@acme.com\"", "\"one@acme.com\""]);
+}
@@ -0,0 +1,261 @@
+use futures::TryStreamExt;
+use log::{info, warn};
+use mongodb::bson::{Bson, Document};
+use mongodb::error::ErrorKind;
+use mongodb::options::IndexOptions;
+use mongodb::{Database, IndexModel};
+use std::collections::BTreeSet;
+
+/// An index a service expects, indexes are created by name at startup
+#[derive(Debug, Clone)]
+pub struct IndexSpec {
+    pub collection: &'static str,
+    pub name: &'static str,
+    pub keys: Document,
+    pub unique: bool,
+}
+
+impl IndexSpec {
+    /// Ascending index over the fields in order
+    pub fn ascending(collection: &'static str, name: &'static str, fields: &[&str]) -> Self {
+        let mut keys = Document::new();
+        for field in fields {
+            keys.insert(*field, 1);
+        }
+        IndexSpec {
+            collection,
+            name,
+            keys,
+            unique: false,
+        }
+    }
+
+    /// Text index for `$text` searches over the fields
+    pub fn text(collection: &'static str, name: &'static str, fields: &[&str]) -> Self {
+        let mut keys = Document::new();
+        for field in fields {
+            keys.insert(*field, "text");
+        }
+        IndexSpec {
+            collection,
+            name,
+            keys,
+            unique: false,
+        }
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    fn model(&self) -> IndexModel {
+        IndexModel::builder()
+            .keys(self.keys.clone())
+            .options(
+                IndexOptions::builder()
+                    .name(self.name.to_string())
+                    .unique(self.unique.then_some(true))
+                    .build(),
+            )
+            .build()
+    }
+}
+
+/// An index whose keys or options differ from its spec, it is left as it is
+#[derive(Debug)]
+pub struct IndexDrift {
+    pub collection: String,
+    pub name: String,
+    pub expected: String,
+    pub found: String,
+}
+
+/// Outcome of `ensure_indexes`, drift is reported but never repaired automatically
+#[derive(Debug, Default)]
+pub struct IndexReport {
+    /// Missing indexes that were created, as `collection.name`
+    pub created: Vec<String>,
+    pub drifted: Vec<IndexDrift>,
+    /// Indexes on the collections that no spec declares, as `collection.name`
+    pub undeclared: Vec<String>,
+    /// Indexes that could not be created with the error, e.g. duplicates for a unique index
+    pub failed: Vec<(String, String)>,
+}
+
+impl IndexReport {
+    pub fn is_clean(&self) -> bool {
+        self.drifted.is_empty() && self.undeclared.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Creates the missing indexes and reports those that differ from the specs.
+/// Existing indexes are matched by name, a changed index has to be dropped by
+/// hand (or by a migration) before it is recreated, so startup never rebuilds
+/// an index on a large collection unexpectedly.
+pub async fn ensure_indexes(
+    database: &Database,
+    specs: &[IndexSpec],
+) -> mongodb::error::Result<IndexReport> {
+    let mut report = IndexReport::default();
+    let collections: BTreeSet<&str> = specs.iter().map(|spec| spec.collection).collect();
+
+    for collection_name in collections {
+        let collection = database.collection::<Document>(collection_name);
+        let existing = list_indexes(database, collection_name).await?;
+
+        for spec in compare_indexes(collection_name, specs, &existing, &mut report) {
+            let qualified = format!("{}.{}", collection_name, spec.name);
+            match collection.create_index(spec.model()).await {
+                Ok(_) => report.created.push(qualified),
+                Err(e) => report.failed.push((qualified, e.to_string())),
+            }
+        }
+    }
+
+    log_report(&report);
+    Ok(report)
+}
+
+/// Compares the indexes found on a collection with the specs for it, adds the
+/// drifted and undeclared ones to the report and returns the specs of the
+/// missing indexes
+pub fn compare_indexes<'a>(
+    collection_name: &str,
+    specs: &'a [IndexSpec],
+    existing: &[IndexModel],
+    report: &mut IndexReport,
+) -> Vec<&'a IndexSpec> {
+    let mut missing = Vec::new();
+    for spec in specs
+        .iter()
+        .filter(|spec| spec.collection == collection_name)
+    {
+        match existing
+            .iter()
+            .find(|index| index_name(index) == Some(spec.name))
+        {
+            Some(index) => {
+                let expected = describe(&spec.keys, spec.unique);
+                let found = describe(&normalized_keys(index), is_unique(index));
+                if expected != found {
+                    report.drifted.push(IndexDrift {
+                        collection: collection_name.to_string(),
+                        name: spec.name.to_string(),
+                        expected,
+                        found,
+                    });
+                }
+            }
+            None => missing.push(spec),
+        }
+    }
+
+    for index in existing {
+        let Some(name) = index_name(index) else {
+            continue;
+        };
+        let declared = specs
+            .iter()
+            .any(|spec| spec.collection == collection_name && spec.name == name);
+        if name != "_id_" && !declared {
+            report
+                .undeclared
+                .push(format!("{}.{}", collection_name, name));
+        }
+    }
+    missing
+}
+
+async fn list_indexes(
+    database: &Database,
+    collection: &str,
+) -> mongodb::error::Result<Vec<IndexModel>> {
+    const NAMESPACE_NOT_FOUND: i32 = 26;
+    match database
+        .collection::<Document>(collection)
+        .list_indexes()
+        .await
+    {
+        Ok(cursor) => cursor.try_collect().await,
+        // Collections are created by their first write, a new database has none
+        Err(e) if matches!(e.kind.as_ref(), ErrorKind::Command(c) if c.code == NAMESPACE_NOT_FOUND) => {
+            Ok(Vec::new())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn index_name(index: &IndexModel) -> Option<&str> {
+    index.options.as_ref()?.name.as_deref()
+}
+
+fn is_unique(index: &IndexModel) -> bool {
+    index
+        .options
+        .as_ref()
+        .and_then(|options| options.unique)
+        .unwrap_or(false)
+}
+
+/// Keys as they were declared, MongoDB stores a text index as `_fts`/`_ftsx`
+/// keys with the indexed fields in the weights
+fn normalized_keys(index: &IndexModel) -> Document {
+    let mut keys = Document::new();
+    for (field, value) in &index.keys {
+        match field.as_str() {
+            "_fts" => {
+                let weights = index
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.weights.clone())
+                    .unwrap_or_default();
+                for text_field in weights.keys() {
+                    keys.insert(text_field, "text");
+                }
+            }
+            "_ftsx" => {}
+            _ => {
+                keys.insert(field, value.clone());
+            }
+        }
+    }
+    keys
+}
+
+/// Comparable description of an index, numeric key directions are compared by value
+fn describe(keys: &Document, unique: bool) -> String {
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|(field, value)| match value {
+            Bson::Int32(direction) => format!("{}: {}", field, direction),
+            Bson::Int64(direction) => format!("{}: {}", field, direction),
+            Bson::Double(direction) => format!("{}: {}", field, *direction as i64),
+            other => format!("{}: {}", field, other),
+        })
+        .collect();
+    let description = format!("{{ {} }}", keys.join(", "));
+    if unique {
+        format!("{} unique", description)
+    } else {
+        description
+    }
+}
+
+fn log_report(report: &IndexReport) {
+    for created in &report.created {
+        info!("Created index {}", created);
+    }
+    for drift in &report.drifted {
+        warn!(
+            "Index {}.{} has drifted, expected {} but found {}",
+            drift.collection, drift.name, drift.expected, drift.found
+        );
+    }
+    for undeclared in &report.undeclared {
+        warn!("Index {} is not declared by the service", undeclared);
+    }
+    for (name, error) in &report.failed {
+        warn!("Failed to create index {}: {}", name, error);
+    }
+}
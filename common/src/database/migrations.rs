@@ -0,0 +1,380 @@
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use log::{debug, info, warn};
+use mongodb::Database;
+use mongodb::bson::{Bson, DateTime, Document, doc, oid::ObjectId};
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::database::mongo_db::is_duplicate_key;
+use crate::utils::load_settings::MigrationSettings;
+
+/// Collection recording applied migrations, it also holds the run lock
+pub const MIGRATIONS_COLLECTION: &str = "_migrations";
+
+const LOCK_ID: &str = "lock";
+
+/// A schema change, migrations run once in version order and are never reverted.
+/// `up` must be safe to run again if a run fails before it is recorded,
+/// e.g. by only matching documents that still need the change.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// Position in the run order, unique within a service and never reused
+    fn version(&self) -> u32;
+
+    fn name(&self) -> &'static str;
+
+    /// Documents `up` would change, reported by a dry run
+    async fn pending_count(&self, database: &Database) -> mongodb::error::Result<u64>;
+
+    /// Applies the change and returns the number of documents changed
+    async fn up(&self, database: &Database) -> mongodb::error::Result<u64>;
+}
+
+/// Errors stopping a migration run
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(mongodb::error::Error),
+    /// Another run took the lock over while this run held it
+    Locked(String),
+    /// The migrations are not in strictly increasing version order
+    InvalidOrder(String),
+    /// A migration failed, the ones before it are recorded as applied
+    Failed {
+        version: u32,
+        name: &'static str,
+        error: mongodb::error::Error,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "Database error: {}", e),
+            MigrationError::Locked(owner) => {
+                write!(f, "Migrations are locked by another run ({})", owner)
+            }
+            MigrationError::InvalidOrder(e) => write!(f, "Invalid migrations: {}", e),
+            MigrationError::Failed {
+                version,
+                name,
+                error,
+            } => write!(f, "Migration {} {} failed: {}", version, name, error),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<mongodb::error::Error> for MigrationError {
+    fn from(e: mongodb::error::Error) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+/// Outcome of one pending migration
+#[derive(Debug, Serialize)]
+pub struct MigrationOutcome {
+    pub version: u32,
+    pub name: &'static str,
+    /// Documents changed, or that would be changed in a dry run
+    pub documents: u64,
+    pub applied: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub pending: Vec<MigrationOutcome>,
+    /// Applied versions this build does not know, the database is ahead of the code
+    pub unknown_versions: Vec<u32>,
+}
+
+/// Runs the pending migrations of a service against its database
+pub struct MigrationRunner {
+    database: Database,
+    migrations: Vec<Box<dyn Migration>>,
+    lock_timeout: Duration,
+    lock_poll: Duration,
+}
+
+impl MigrationRunner {
+    pub fn new(
+        database: Database,
+        migrations: Vec<Box<dyn Migration>>,
+        settings: &MigrationSettings,
+    ) -> Result<Self, MigrationError> {
+        for pair in migrations.windows(2) {
+            if pair[0].version() >= pair[1].version() {
+                return Err(MigrationError::InvalidOrder(format!(
+                    "version {} ({}) must come after version {} ({})",
+                    pair[1].version(),
+                    pair[1].name(),
+                    pair[0].version(),
+                    pair[0].name()
+                )));
+            }
+        }
+        Ok(MigrationRunner {
+            database,
+            migrations,
+            lock_timeout: Duration::from_secs(settings.lock_timeout_secs),
+            lock_poll: Duration::from_millis(settings.lock_poll_ms),
+        })
+    }
+
+    /// Applies the pending migrations in order under the lock, stops at the first failure.
+    /// When another run holds the lock it waits for the lock to be released or to
+    /// expire, the migrations that run applied are then no longer pending.
+    pub async fn run(&self) -> Result<MigrationReport, MigrationError> {
+        let owner = self.wait_for_lock().await?;
+        let result = self.apply_pending(&owner).await;
+        self.release_lock(&owner).await;
+        result
+    }
+
+    /// Reports what `run` would do without changing anything or taking the lock
+    pub async fn dry_run(&self) -> Result<MigrationReport, MigrationError> {
+        let applied = self.applied_versions().await?;
+        let mut report = self.report(&applied, true);
+        for migration in self.pending(&applied) {
+            report.pending.push(MigrationOutcome {
+                version: migration.version(),
+                name: migration.name(),
+                documents: migration.pending_count(&self.database).await?,
+                applied: false,
+            });
+        }
+        Ok(report)
+    }
+
+    async fn apply_pending(&self, owner: &str) -> Result<MigrationReport, MigrationError> {
+        let applied = self.applied_versions().await?;
+        let mut report = self.report(&applied, false);
+        for migration in self.pending(&applied) {
+            let (version, name) = (migration.version(), migration.name());
+            self.refresh_lock(owner).await?;
+            info!("Applying migration {} {}", version, name);
+            let started = Instant::now();
+            let documents =
+                migration
+                    .up(&self.database)
+                    .await
+                    .map_err(|error| MigrationError::Failed {
+                        version,
+                        name,
+                        error,
+                    })?;
+            // A step outlasting the lock timeout may have lost the lock, `up`
+            // is safe to run again so it is left unrecorded for the new holder
+            self.refresh_lock(owner).await?;
+            self.collection()
+                .insert_one(doc! {
+                    "_id": i64::from(version),
+                    "name": name,
+                    "documents": documents as i64,
+                    "duration_ms": started.elapsed().as_millis() as i64,
+                    "applied_at": DateTime::now(),
+                })
+                .await?;
+            info!(
+                "Applied migration {} {}, {} documents changed",
+                version, name, documents
+            );
+            report.pending.push(MigrationOutcome {
+                version,
+                name,
+                documents,
+                applied: true,
+            });
+        }
+        Ok(report)
+    }
+
+    /// The migrations whose version is not in `applied`, in run order
+    pub fn pending<'a>(&'a self, applied: &'a [u32]) -> impl Iterator<Item = &'a dyn Migration> {
+        self.migrations
+            .iter()
+            .map(Box::as_ref)
+            .filter(|migration| !applied.contains(&migration.version()))
+    }
+
+    /// An empty report for a database with the `applied` versions, listing the
+    /// ones this build does not know
+    pub fn report(&self, applied: &[u32], dry_run: bool) -> MigrationReport {
+        let unknown_versions: Vec<u32> = applied
+            .iter()
+            .copied()
+            .filter(|version| {
+                !self
+                    .migrations
+                    .iter()
+                    .any(|migration| migration.version() == *version)
+            })
+            .collect();
+        if !unknown_versions.is_empty() {
+            warn!(
+                "Database has migrations {:?} applied that this build does not know",
+                unknown_versions
+            );
+        }
+        MigrationReport {
+            dry_run,
+            pending: Vec::new(),
+            unknown_versions,
+        }
+    }
+
+    async fn applied_versions(&self) -> Result<Vec<u32>, MigrationError> {
+        let records: Vec<Document> = self
+            .collection()
+            .find(doc! { "_id": { "$type": "number" } })
+            .await?
+            .try_collect()
+            .await?;
+        Ok(records
+            .iter()
+            .filter_map(|record| match record.get("_id") {
+                Some(Bson::Int64(version)) => u32::try_from(*version).ok(),
+                Some(Bson::Int32(version)) => u32::try_from(*version).ok(),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Polls `acquire_lock` until the lock is released by its holder or expires
+    async fn wait_for_lock(&self) -> Result<String, MigrationError> {
+        let mut waiting = false;
+        loop {
+            match self.acquire_lock().await {
+                Err(MigrationError::Locked(holder)) => {
+                    if waiting {
+                        debug!("Migration lock still held by {}", holder);
+                    } else {
+                        info!("Waiting for the migration lock held by {}", holder);
+                        waiting = true;
+                    }
+                    tokio::time::sleep(self.lock_poll).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Takes the lock, replacing it when its holder has not released it within the timeout
+    async fn acquire_lock(&self) -> Result<String, MigrationError> {
+        let owner = format!("{}-{}", std::process::id(), ObjectId::new().to_hex());
+        let lock = doc! { "_id": LOCK_ID, "owner": &owner, "locked_at": DateTime::now() };
+        match self.collection().insert_one(lock.clone()).await {
+            Ok(_) => return Ok(owner),
+            Err(e) if !is_duplicate_key(&e) => return Err(e.into()),
+            Err(_) => {}
+        }
+
+        let expired_before = DateTime::from_millis(
+            DateTime::now().timestamp_millis() - self.lock_timeout.as_millis() as i64,
+        );
+        let replaced = self
+            .collection()
+            .replace_one(
+                doc! { "_id": LOCK_ID, "locked_at": { "$lt": expired_before } },
+                lock,
+            )
+            .await?;
+        if replaced.modified_count == 1 {
+            warn!(
+                "Replaced a migration lock older than {:?}",
+                self.lock_timeout
+            );
+            return Ok(owner);
+        }
+
+        Err(MigrationError::Locked(self.lock_holder().await?))
+    }
+
+    /// Moves `locked_at` forward so a long run is not taken over as expired,
+    /// fails when another run has taken the lock in the meantime
+    async fn refresh_lock(&self, owner: &str) -> Result<(), MigrationError> {
+        let refreshed = self
+            .collection()
+            .update_one(
+                doc! { "_id": LOCK_ID, "owner": owner },
+                doc! { "$set": { "locked_at": DateTime::now() } },
+            )
+            .await?;
+        if refreshed.matched_count == 0 {
+            warn!("Lost the migration lock, stopping the run");
+            return Err(MigrationError::Locked(self.lock_holder().await?));
+        }
+        Ok(())
+    }
+
+    async fn lock_holder(&self) -> Result<String, MigrationError> {
+        Ok(self
+            .collection()
+            .find_one(doc! { "_id": LOCK_ID })
+            .await?
+            .and_then(|lock| lock.get_str("owner").ok().map(str::to_string))
+            .unwrap_or_default())
+    }
+
+    async fn release_lock(&self, owner: &str) {
+        if let Err(e) = self
+            .collection()
+            .delete_one(doc! { "_id": LOCK_ID, "owner": owner })
+            .await
+        {
+            warn!("Failed to release the migration lock: {}", e);
+        }
+    }
+
+    fn collection(&self) -> mongodb::Collection<Document> {
+        self.database.collection(MIGRATIONS_COLLECTION)
+    }
+}
+
+/// Sets a field on the documents of a collection that do not have it yet,
+/// the usual migration when a field with a default is added to a model
+pub struct SetMissingField {
+    pub version: u32,
+    pub name: &'static str,
+    pub collection: &'static str,
+    pub field: &'static str,
+    pub value: Bson,
+}
+
+impl SetMissingField {
+    fn filter(&self) -> Document {
+        doc! { self.field: { "$exists": false } }
+    }
+}
+
+#[async_trait]
+impl Migration for SetMissingField {
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn pending_count(&self, database: &Database) -> mongodb::error::Result<u64> {
+        database
+            .collection::<Document>(self.collection)
+            .count_documents(self.filter())
+            .await
+    }
+
+    async fn up(&self, database: &Database) -> mongodb::error::Result<u64> {
+        let result = database
+            .collection::<Document>(self.collection)
+            .update_many(
+                self.filter(),
+                doc! { "$set": { self.field: self.value.clone() } },
+            )
+            .await?;
+        Ok(result.modified_count)
+    }
+}
@@ -0,0 +1,44 @@
+use crate::utils::load_settings::DatabaseSettings;
+use anyhow::{Context, Result};
+use mongodb::bson::doc;
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::{Client, Database, options::ClientOptions};
+
+/// Connection pool shared by every repository of a service, clones share the pool
+#[derive(Clone)]
+pub struct MongoDb {
+    pub client: Client,
+    pub database: Database,
+}
+
+impl MongoDb {
+    /// Connects using already loaded settings, the client connects lazily
+    /// so this only fails on a malformed URI or options
+    pub async fn connect(settings: &DatabaseSettings) -> Result<Self> {
+        let client_options = ClientOptions::parse(&settings.remote_uri)
+            .await
+            .context("Invalid database.remote_uri")?;
+        let client = Client::with_options(client_options).context("Invalid MongoDB options")?;
+
+        let database = client.database(&settings.database_name);
+        Ok(MongoDb { client, database })
+    }
+
+    /// Round trip to the server, used at startup to wait until MongoDB is reachable
+    pub async fn ping(&self) -> mongodb::error::Result<()> {
+        self.database.run_command(doc! {"ping": 1}).await?;
+        Ok(())
+    }
+}
+
+/// Whether a write was refused by a unique index
+pub fn is_duplicate_key(e: &mongodb::error::Error) -> bool {
+    const DUPLICATE_KEY: i32 = 11000;
+    match e.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => {
+            write_error.code == DUPLICATE_KEY
+        }
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
@@ -0,0 +1,38 @@
+use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Media type of problem detail responses (RFC 7807)
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem detail body (RFC 7807), `code` is a stable machine readable
+/// error code clients can match on instead of the human readable detail.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+}
+
+impl ProblemDetails {
+    pub fn new(status: StatusCode, code: &str, detail: impl Into<String>) -> Self {
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            code: code.to_string(),
+        }
+    }
+
+    /// Builds the response, callers can add headers before sending it
+    pub fn to_response(&self) -> HttpResponse {
+        HttpResponse::build(StatusCode::from_u16(self.status).unwrap_or_default())
+            .content_type(PROBLEM_JSON)
+            .json(self)
+    }
+}
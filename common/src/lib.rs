@@ -0,0 +1,52 @@
+pub mod database {
+    pub mod indexes;
+    pub mod migrations;
+    pub mod mongo_db;
+}
+
+pub mod utils {
+    pub mod load_settings;
+    pub mod openapi;
+    pub mod password_utils;
+    pub mod retry;
+    pub mod shutdown;
+    pub mod telemetry;
+}
+
+pub mod models {
+    pub mod address;
+    pub mod contact;
+    pub mod customer;
+    pub mod health;
+    pub mod schema;
+    pub mod tenant;
+}
+
+pub mod errors {
+    pub mod problem;
+}
+
+pub mod middleware {
+    pub mod authentication;
+    pub mod metrics;
+    pub mod request_tracing;
+}
+
+pub mod services {
+    pub mod certification;
+    pub mod health;
+    pub mod metrics;
+}
+
+pub mod routes {
+    pub mod health;
+    pub mod metrics;
+}
+
+pub mod messaging {
+    pub mod bus;
+    pub mod connection;
+    pub mod in_memory;
+    pub mod lapin_bus;
+    pub mod trace_context;
+}
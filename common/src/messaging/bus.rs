@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Headers sent along with a message, they carry the trace context and request id
+pub type MessageHeaders = BTreeMap<String, String>;
+
+/// How an exchange routes messages to the queues bound to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExchangeKind {
+    /// The routing key must equal the binding key
+    Direct,
+    /// Binding keys are dot separated patterns, `*` matches one word and `#` zero or more
+    Topic,
+    /// Every bound queue gets every message
+    Fanout,
+}
+
+/// Errors raised by a message bus
+#[derive(Debug)]
+pub enum MessagingError {
+    /// The broker refused the operation or the connection failed
+    Broker(String),
+    UnknownExchange(String),
+    UnknownQueue(String),
+}
+
+impl fmt::Display for MessagingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessagingError::Broker(e) => write!(f, "{}", e),
+            MessagingError::UnknownExchange(name) => write!(f, "Unknown exchange {}", name),
+            MessagingError::UnknownQueue(name) => write!(f, "Unknown queue {}", name),
+        }
+    }
+}
+
+impl std::error::Error for MessagingError {}
+
+impl From<lapin::Error> for MessagingError {
+    fn from(e: lapin::Error) -> Self {
+        MessagingError::Broker(e.to_string())
+    }
+}
+
+/// Settles a delivery with the broker it came from
+#[async_trait]
+pub trait Acknowledger: Send + Sync {
+    async fn ack(&self) -> Result<(), MessagingError>;
+
+    async fn nack(&self, requeue: bool) -> Result<(), MessagingError>;
+}
+
+/// A consumed message, it must be acknowledged once handled or rejected with `nack`
+pub struct Delivery {
+    pub routing_key: String,
+    pub payload: Vec<u8>,
+    pub headers: MessageHeaders,
+    acknowledger: Box<dyn Acknowledger>,
+}
+
+impl Delivery {
+    pub fn new(
+        routing_key: String,
+        payload: Vec<u8>,
+        headers: MessageHeaders,
+        acknowledger: Box<dyn Acknowledger>,
+    ) -> Self {
+        Delivery {
+            routing_key,
+            payload,
+            headers,
+            acknowledger,
+        }
+    }
+
+    pub async fn ack(self) -> Result<(), MessagingError> {
+        self.acknowledger.ack().await
+    }
+
+    /// Rejects the message, with `requeue` it is delivered again, otherwise it is dropped
+    pub async fn nack(self, requeue: bool) -> Result<(), MessagingError> {
+        self.acknowledger.nack(requeue).await
+    }
+}
+
+/// Deliveries of one consumer on a queue
+#[async_trait]
+pub trait Subscription: Send {
+    /// The next delivery, `None` once the subscription is cancelled or the connection closed
+    async fn next(&mut self) -> Option<Result<Delivery, MessagingError>>;
+
+    /// Stops the broker delivering to this consumer
+    async fn cancel(&mut self) -> Result<(), MessagingError>;
+}
+
+/// Publish/subscribe messaging between services, implemented over RabbitMQ
+/// and in process so the event flow can be tested without a broker.
+/// Declarations are idempotent, every service declares what it uses.
+#[async_trait]
+pub trait MessageBus: Send + Sync {
+    async fn declare_exchange(&self, name: &str, kind: ExchangeKind) -> Result<(), MessagingError>;
+
+    async fn declare_queue(&self, name: &str) -> Result<(), MessagingError>;
+
+    /// Routes messages of the exchange matching `binding_key` to the queue
+    async fn bind_queue(
+        &self,
+        queue: &str,
+        exchange: &str,
+        binding_key: &str,
+    ) -> Result<(), MessagingError>;
+
+    /// Publishes a message, it is dropped when no queue is bound for the routing key
+    async fn publish(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: Vec<u8>,
+        headers: MessageHeaders,
+    ) -> Result<(), MessagingError>;
+
+    async fn subscribe(
+        &self,
+        queue: &str,
+        consumer_tag: &str,
+    ) -> Result<Box<dyn Subscription>, MessagingError>;
+
+    /// Whether messages can currently be published and consumed
+    fn is_connected(&self) -> bool;
+
+    /// Closes the connection, unacknowledged deliveries are requeued by the broker
+    async fn close(&self);
+}
@@ -0,0 +1,76 @@
+use lapin::{
+    Channel, Connection, ConnectionProperties, ExchangeKind, options::*, types::FieldTable,
+};
+
+use log::{info, warn};
+
+use crate::utils::load_settings::MessagingSettings;
+
+/// Opens the AMQP connection, keep it to close it on shutdown
+pub async fn create_connection(settings: &MessagingSettings) -> Result<Connection, lapin::Error> {
+    let connection_properties = ConnectionProperties::default()
+        .with_executor(tokio_executor_trait::Tokio::current())
+        .with_reactor(tokio_reactor_trait::Tokio);
+
+    let connection = Connection::connect(&settings.url, connection_properties).await?;
+
+    Ok(connection)
+}
+
+pub async fn create_channel(connection: &Connection) -> Result<Channel, lapin::Error> {
+    info!("Creating channel");
+    connection.create_channel().await
+}
+
+/// Closes the connection and its channels, unacknowledged deliveries are requeued by the broker
+pub async fn close_connection(connection: &Connection) {
+    if let Err(e) = connection.close(200, "Shutting down").await {
+        warn!("Failed to close AMQP connection: {}", e);
+    }
+}
+
+pub async fn declare_exchange(
+    channel: &Channel,
+    name: &str,
+    kind: ExchangeKind,
+) -> Result<(), lapin::Error> {
+    channel
+        .exchange_declare(
+            name,
+            kind,
+            ExchangeDeclareOptions {
+                passive: false,
+                durable: true,
+                auto_delete: false,
+                internal: false,
+                nowait: false,
+            },
+            FieldTable::default(),
+        )
+        .await
+}
+
+pub async fn declare_queue(channel: &Channel, name: &str) -> Result<(), lapin::Error> {
+    channel
+        .queue_declare(name, QueueDeclareOptions::default(), FieldTable::default())
+        .await?;
+    Ok(())
+}
+
+/// Binds a queue to an exchange, routing keys may use topic wildcards
+pub async fn bind_queue(
+    channel: &Channel,
+    queue: &str,
+    exchange: &str,
+    routing_key: &str,
+) -> Result<(), lapin::Error> {
+    channel
+        .queue_bind(
+            queue,
+            exchange,
+            routing_key,
+            QueueBindOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+}
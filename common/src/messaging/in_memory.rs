@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::messaging::bus::{
+    Acknowledger, Delivery, ExchangeKind, MessageBus, MessageHeaders, MessagingError, Subscription,
+};
+
+/// In-process broker for tests and running without RabbitMQ, exchanges route
+/// by kind and routing key like RabbitMQ does. Messages are lost on restart.
+#[derive(Clone, Default)]
+pub struct InMemoryMessageBus {
+    broker: Arc<Mutex<Broker>>,
+}
+
+#[derive(Default)]
+struct Broker {
+    exchanges: HashMap<String, Exchange>,
+    queues: HashMap<String, Arc<Queue>>,
+}
+
+struct Exchange {
+    kind: ExchangeKind,
+    /// Queue names with their binding keys
+    bindings: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+struct Queue {
+    messages: Mutex<VecDeque<Message>>,
+    available: Notify,
+}
+
+#[derive(Clone)]
+struct Message {
+    routing_key: String,
+    payload: Vec<u8>,
+    headers: MessageHeaders,
+}
+
+impl Queue {
+    fn push(&self, message: Message) {
+        self.messages.lock().unwrap().push_back(message);
+        self.available.notify_one();
+    }
+}
+
+impl InMemoryMessageBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of messages waiting in the queue, unknown queues have none
+    pub fn queue_len(&self, queue: &str) -> usize {
+        let broker = self.broker.lock().unwrap();
+        broker
+            .queues
+            .get(queue)
+            .map_or(0, |queue| queue.messages.lock().unwrap().len())
+    }
+
+    fn queue(&self, name: &str) -> Result<Arc<Queue>, MessagingError> {
+        let broker = self.broker.lock().unwrap();
+        broker
+            .queues
+            .get(name)
+            .cloned()
+            .ok_or_else(|| MessagingError::UnknownQueue(name.to_string()))
+    }
+}
+
+#[async_trait]
+impl MessageBus for InMemoryMessageBus {
+    async fn declare_exchange(&self, name: &str, kind: ExchangeKind) -> Result<(), MessagingError> {
+        let mut broker = self.broker.lock().unwrap();
+        match broker.exchanges.get(name) {
+            Some(exchange) if exchange.kind != kind => Err(MessagingError::Broker(format!(
+                "Exchange {} already declared as {:?}",
+                name, exchange.kind
+            ))),
+            Some(_) => Ok(()),
+            None => {
+                broker.exchanges.insert(
+                    name.to_string(),
+                    Exchange {
+                        kind,
+                        bindings: Vec::new(),
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn declare_queue(&self, name: &str) -> Result<(), MessagingError> {
+        let mut broker = self.broker.lock().unwrap();
+        broker.queues.entry(name.to_string()).or_default();
+        Ok(())
+    }
+
+    async fn bind_queue(
+        &self,
+        queue: &str,
+        exchange: &str,
+        binding_key: &str,
+    ) -> Result<(), MessagingError> {
+        let mut broker = self.broker.lock().unwrap();
+        if !broker.queues.contains_key(queue) {
+            return Err(MessagingError::UnknownQueue(queue.to_string()));
+        }
+        let exchange = broker
+            .exchanges
+            .get_mut(exchange)
+            .ok_or_else(|| MessagingError::UnknownExchange(exchange.to_string()))?;
+        let binding = (queue.to_string(), binding_key.to_string());
+        if !exchange.bindings.contains(&binding) {
+            exchange.bindings.push(binding);
+        }
+        Ok(())
+    }
+
+    async fn publish(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: Vec<u8>,
+        headers: MessageHeaders,
+    ) -> Result<(), MessagingError> {
+        let broker = self.broker.lock().unwrap();
+        let message = Message {
+            routing_key: routing_key.to_string(),
+            payload,
+            headers,
+        };
+
+        // The default exchange routes to the queue named by the routing key
+        if exchange.is_empty() {
+            if let Some(queue) = broker.queues.get(routing_key) {
+                queue.push(message);
+            }
+            return Ok(());
+        }
+
+        let exchange = broker
+            .exchanges
+            .get(exchange)
+            .ok_or_else(|| MessagingError::UnknownExchange(exchange.to_string()))?;
+        let mut routed: Vec<&str> = exchange
+            .bindings
+            .iter()
+            .filter(|(_, binding_key)| match exchange.kind {
+                ExchangeKind::Direct => binding_key == routing_key,
+                ExchangeKind::Topic => topic_matches(binding_key, routing_key),
+                ExchangeKind::Fanout => true,
+            })
+            .map(|(queue, _)| queue.as_str())
+            .collect();
+        // A queue bound with several matching keys still gets the message once
+        routed.sort_unstable();
+        routed.dedup();
+        for queue in routed {
+            if let Some(queue) = broker.queues.get(queue) {
+                queue.push(message.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        queue: &str,
+        _consumer_tag: &str,
+    ) -> Result<Box<dyn Subscription>, MessagingError> {
+        Ok(Box::new(InMemorySubscription {
+            queue: self.queue(queue)?,
+            cancelled: false,
+        }))
+    }
+
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    async fn close(&self) {}
+}
+
+struct InMemorySubscription {
+    queue: Arc<Queue>,
+    cancelled: bool,
+}
+
+#[async_trait]
+impl Subscription for InMemorySubscription {
+    async fn next(&mut self) -> Option<Result<Delivery, MessagingError>> {
+        loop {
+            if self.cancelled {
+                return None;
+            }
+            let message = self.queue.messages.lock().unwrap().pop_front();
+            if let Some(message) = message {
+                let acknowledger = InMemoryAcknowledger {
+                    queue: self.queue.clone(),
+                    message: message.clone(),
+                };
+                return Some(Ok(Delivery::new(
+                    message.routing_key,
+                    message.payload,
+                    message.headers,
+                    Box::new(acknowledger),
+                )));
+            }
+            // A message pushed after the check above leaves a permit, so it is not missed
+            self.queue.available.notified().await;
+        }
+    }
+
+    async fn cancel(&mut self) -> Result<(), MessagingError> {
+        self.cancelled = true;
+        Ok(())
+    }
+}
+
+struct InMemoryAcknowledger {
+    queue: Arc<Queue>,
+    message: Message,
+}
+
+#[async_trait]
+impl Acknowledger for InMemoryAcknowledger {
+    async fn ack(&self) -> Result<(), MessagingError> {
+        Ok(())
+    }
+
+    async fn nack(&self, requeue: bool) -> Result<(), MessagingError> {
+        if requeue {
+            self.queue
+                .messages
+                .lock()
+                .unwrap()
+                .push_front(self.message.clone());
+            self.queue.available.notify_one();
+        }
+        Ok(())
+    }
+}
+
+/// Matches a routing key against a topic binding key, words are separated
+/// by dots, `*` matches exactly one word and `#` zero or more words
+fn topic_matches(binding_key: &str, routing_key: &str) -> bool {
+    fn matches(pattern: &[&str], words: &[&str]) -> bool {
+        match pattern.split_first() {
+            None => words.is_empty(),
+            Some((&"#", rest)) => (0..=words.len()).any(|skip| matches(rest, &words[skip..])),
+            Some((&"*", rest)) => !words.is_empty() && matches(rest, &words[1..]),
+            Some((word, rest)) => words.first() == Some(word) && matches(rest, &words[1..]),
+        }
+    }
+    let pattern: Vec<&str> = binding_key.split('.').collect();
+    let words: Vec<&str> = routing_key.split('.').collect();
+    matches(&pattern, &words)
+}
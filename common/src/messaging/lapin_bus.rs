@@ -0,0 +1,184 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use lapin::acker::Acker;
+use lapin::options::{BasicAckOptions, BasicCancelOptions, BasicNackOptions, BasicPublishOptions};
+use lapin::types::{AMQPValue, FieldTable, ShortString};
+use lapin::{BasicProperties, Channel, Connection, Consumer};
+
+use crate::messaging::bus::{
+    Acknowledger, Delivery, ExchangeKind, MessageBus, MessageHeaders, MessagingError, Subscription,
+};
+use crate::messaging::connection::{
+    bind_queue, close_connection, create_channel, create_connection, declare_exchange,
+    declare_queue,
+};
+use crate::utils::load_settings::MessagingSettings;
+
+/// Message bus over a RabbitMQ connection with a single channel
+pub struct LapinMessageBus {
+    connection: Connection,
+    channel: Channel,
+}
+
+impl LapinMessageBus {
+    pub async fn connect(settings: &MessagingSettings) -> Result<Self, lapin::Error> {
+        let connection = create_connection(settings).await?;
+        let channel = create_channel(&connection).await?;
+        Ok(LapinMessageBus {
+            connection,
+            channel,
+        })
+    }
+}
+
+#[async_trait]
+impl MessageBus for LapinMessageBus {
+    async fn declare_exchange(&self, name: &str, kind: ExchangeKind) -> Result<(), MessagingError> {
+        let kind = match kind {
+            ExchangeKind::Direct => lapin::ExchangeKind::Direct,
+            ExchangeKind::Topic => lapin::ExchangeKind::Topic,
+            ExchangeKind::Fanout => lapin::ExchangeKind::Fanout,
+        };
+        Ok(declare_exchange(&self.channel, name, kind).await?)
+    }
+
+    async fn declare_queue(&self, name: &str) -> Result<(), MessagingError> {
+        Ok(declare_queue(&self.channel, name).await?)
+    }
+
+    async fn bind_queue(
+        &self,
+        queue: &str,
+        exchange: &str,
+        binding_key: &str,
+    ) -> Result<(), MessagingError> {
+        Ok(bind_queue(&self.channel, queue, exchange, binding_key).await?)
+    }
+
+    async fn publish(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: Vec<u8>,
+        headers: MessageHeaders,
+    ) -> Result<(), MessagingError> {
+        let properties = BasicProperties::default().with_headers(to_field_table(headers));
+        self.channel
+            .basic_publish(
+                exchange,
+                routing_key,
+                BasicPublishOptions::default(),
+                &payload,
+                properties,
+            )
+            .await?
+            .await?;
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        queue: &str,
+        consumer_tag: &str,
+    ) -> Result<Box<dyn Subscription>, MessagingError> {
+        let consumer = self
+            .channel
+            .basic_consume(
+                queue,
+                consumer_tag,
+                Default::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        Ok(Box::new(LapinSubscription {
+            channel: self.channel.clone(),
+            consumer_tag: consumer_tag.to_string(),
+            consumer,
+        }))
+    }
+
+    fn is_connected(&self) -> bool {
+        self.channel.status().connected()
+    }
+
+    async fn close(&self) {
+        close_connection(&self.connection).await;
+    }
+}
+
+struct LapinSubscription {
+    channel: Channel,
+    consumer_tag: String,
+    consumer: Consumer,
+}
+
+#[async_trait]
+impl Subscription for LapinSubscription {
+    async fn next(&mut self) -> Option<Result<Delivery, MessagingError>> {
+        let delivery = match self.consumer.next().await? {
+            Ok(delivery) => delivery,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .map(from_field_table)
+            .unwrap_or_default();
+        Some(Ok(Delivery::new(
+            delivery.routing_key.to_string(),
+            delivery.data,
+            headers,
+            Box::new(LapinAcknowledger(delivery.acker)),
+        )))
+    }
+
+    async fn cancel(&mut self) -> Result<(), MessagingError> {
+        self.channel
+            .basic_cancel(&self.consumer_tag, BasicCancelOptions::default())
+            .await?;
+        Ok(())
+    }
+}
+
+struct LapinAcknowledger(Acker);
+
+#[async_trait]
+impl Acknowledger for LapinAcknowledger {
+    async fn ack(&self) -> Result<(), MessagingError> {
+        self.0.ack(BasicAckOptions::default()).await?;
+        Ok(())
+    }
+
+    async fn nack(&self, requeue: bool) -> Result<(), MessagingError> {
+        self.0
+            .nack(BasicNackOptions {
+                requeue,
+                ..BasicNackOptions::default()
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+fn to_field_table(headers: MessageHeaders) -> FieldTable {
+    let mut table = FieldTable::default();
+    for (key, value) in headers {
+        table.insert(ShortString::from(key), AMQPValue::LongString(value.into()));
+    }
+    table
+}
+
+/// String headers of a message, headers of other types are not used between the services
+fn from_field_table(table: &FieldTable) -> MessageHeaders {
+    table
+        .inner()
+        .iter()
+        .filter_map(|(key, value)| match value {
+            AMQPValue::LongString(value) => std::str::from_utf8(value.as_bytes())
+                .ok()
+                .map(|value| (key.to_string(), value.to_string())),
+            _ => None,
+        })
+        .collect()
+}
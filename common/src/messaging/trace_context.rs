@@ -0,0 +1,39 @@
+use opentelemetry::Context;
+use opentelemetry::propagation::{Extractor, Injector};
+
+use crate::messaging::bus::MessageHeaders;
+use crate::utils::telemetry::{extract_context, inject_context};
+
+/// Message headers carrying the trace context and request id, publish them
+/// along with the message
+pub fn trace_headers(cx: &Context) -> MessageHeaders {
+    let mut headers = MessageHeaders::new();
+    inject_context(cx, &mut HeadersInjector(&mut headers));
+    headers
+}
+
+/// Continues the trace of a consumed message, a new trace is started for
+/// messages published without trace headers
+pub fn message_context(headers: &MessageHeaders) -> Context {
+    extract_context(&HeadersExtractor(headers))
+}
+
+struct HeadersInjector<'a>(&'a mut MessageHeaders);
+
+impl Injector for HeadersInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+struct HeadersExtractor<'a>(&'a MessageHeaders);
+
+impl Extractor for HeadersExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
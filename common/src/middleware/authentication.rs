@@ -0,0 +1,172 @@
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform, forward_ready};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{Header, HeaderValue, WWW_AUTHENTICATE};
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError, web};
+use actix_web_httpauth::headers::authorization::{Authorization, Bearer};
+use futures::future::{LocalBoxFuture, Ready, ready};
+use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::errors::problem::ProblemDetails;
+use crate::services::certification::{CertificateService, Claims};
+
+/// Why a request was refused, rendered as 401 or 403 with a
+/// `WWW-Authenticate` challenge as described in RFC 6750.
+#[derive(Debug)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    InsufficientScope(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "Missing bearer token"),
+            AuthError::InvalidToken => write!(f, "Invalid token"),
+            AuthError::InsufficientScope(_) => write!(f, "Required access scope not found"),
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::MissingToken | AuthError::InvalidToken => StatusCode::UNAUTHORIZED,
+            AuthError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let (code, challenge) = match self {
+            AuthError::MissingToken => ("missing_token", "Bearer".to_string()),
+            AuthError::InvalidToken => (
+                "invalid_token",
+                "Bearer error=\"invalid_token\"".to_string(),
+            ),
+            AuthError::InsufficientScope(scope) => (
+                "insufficient_scope",
+                format!("Bearer error=\"insufficient_scope\", scope=\"{}\"", scope),
+            ),
+        };
+        let mut response =
+            ProblemDetails::new(self.status_code(), code, self.to_string()).to_response();
+        if let Ok(challenge) = HeaderValue::from_str(&challenge) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+        }
+        response
+    }
+}
+
+/// Verified claims of the bearer token, extract it in a route to require
+/// a valid token. Claims already verified by `RequireScope` are reused.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(pub Claims);
+
+impl AuthenticatedUser {
+    /// Fails with 403 when the token does not carry the scope
+    pub fn require_scope(&self, scope: &str) -> Result<(), AuthError> {
+        if self.0.scopes.iter().any(|s| s == scope) {
+            Ok(())
+        } else {
+            Err(AuthError::InsufficientScope(scope.to_string()))
+        }
+    }
+
+    pub fn into_claims(self) -> Claims {
+        self.0
+    }
+}
+
+impl Deref for AuthenticatedUser {
+    type Target = Claims;
+
+    fn deref(&self) -> &Claims {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(claims) = req.extensions().get::<Claims>() {
+            return ready(Ok(AuthenticatedUser(claims.clone())));
+        }
+        ready(authenticate(req).map(AuthenticatedUser))
+    }
+}
+
+/// Reads the bearer token and verifies it with the app's CertificateService
+fn authenticate(req: &HttpRequest) -> Result<Claims, AuthError> {
+    let token = bearer_token(req)?;
+    certificate_service(req)
+        .verify_token(&token)
+        .map_err(|_| AuthError::InvalidToken)
+}
+
+fn bearer_token(req: &HttpRequest) -> Result<String, AuthError> {
+    Authorization::<Bearer>::parse(req)
+        .map(|authorization| authorization.into_scheme().token().to_string())
+        .map_err(|_| AuthError::MissingToken)
+}
+
+fn certificate_service(req: &HttpRequest) -> &web::Data<CertificateService> {
+    req.app_data::<web::Data<CertificateService>>()
+        .expect("CertificateService is not registered as app data")
+}
+
+/// Middleware rejecting requests whose token lacks the scope, use it with
+/// `wrap` on a scope or resource, or `#[get("/path", wrap = "RequireScope(\"scope\")")]`.
+#[derive(Clone, Copy)]
+pub struct RequireScope(pub &'static str);
+
+impl<S, B> Transform<S, ServiceRequest> for RequireScope
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequireScopeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireScopeMiddleware {
+            service: Rc::new(service),
+            scope: self.0,
+        }))
+    }
+}
+
+pub struct RequireScopeMiddleware<S> {
+    service: Rc<S>,
+    scope: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireScopeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let scope = self.scope;
+
+        Box::pin(async move {
+            let token = bearer_token(req.request())?;
+            let claims = certificate_service(req.request()).authorize(&token, scope)?;
+            req.extensions_mut().insert(claims);
+            service.call(req).await
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use actix_web::Error;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready};
+use futures::future::{LocalBoxFuture, Ready, ready};
+use std::time::Instant;
+
+use crate::services::metrics::{HTTP_REQUEST_DURATION, HTTP_REQUESTS};
+
+/// Records request counts and latency, wrap the whole App with it.
+/// Requests are labelled by route pattern so ids in paths do not create new series.
+#[derive(Clone, Copy, Default)]
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddleware { service }))
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| "unmatched".to_string());
+        let response = self.service.call(req);
+
+        Box::pin(async move {
+            let result = response.await;
+            let status = match &result {
+                Ok(response) => response.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            let labels = [method.as_str(), route.as_str(), status.as_str()];
+            HTTP_REQUESTS.with_label_values(&labels).inc();
+            HTTP_REQUEST_DURATION
+                .with_label_values(&labels)
+                .observe(started.elapsed().as_secs_f64());
+            result
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{Error, HttpMessage};
+use futures::future::{LocalBoxFuture, Ready, ready};
+use opentelemetry::context::FutureExt;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{KeyValue, global};
+use std::rc::Rc;
+use uuid::Uuid;
+
+use crate::utils::telemetry::{REQUEST_ID_HEADER, RequestId, TRACER_NAME, extract_http_context};
+
+/// Access log format of the actix `Logger`, includes the request id
+pub const LOG_FORMAT: &str = r#"%a "%r" %s %b %{x-request-id}o %T"#;
+
+/// Request ids longer than this are replaced rather than trusted
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Gives every request an id and a server span, wrap the App with it inside the `Logger`.
+/// The id is taken from the `X-Request-ID` header or generated, returned in the
+/// response and available to routes as the `RequestId` request extension.
+/// The span continues the caller's trace when a `traceparent` header is sent and
+/// is the current context while the handler runs, so Mongo calls and published
+/// messages become part of the trace.
+#[derive(Clone, Copy, Default)]
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestTracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTracingMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN)
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| "unmatched".to_string());
+
+        let tracer = global::tracer(TRACER_NAME);
+        let parent = extract_http_context(req.headers());
+        let span = tracer
+            .span_builder(format!("{} {}", req.method(), route))
+            .with_kind(SpanKind::Server)
+            .with_attributes([
+                KeyValue::new("http.request.method", req.method().to_string()),
+                KeyValue::new("http.route", route),
+                KeyValue::new("request.id", request_id.clone()),
+            ])
+            .start_with_context(&tracer, &parent);
+        let cx = parent
+            .with_span(span)
+            .with_value(RequestId(request_id.clone()));
+
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+        let service = Rc::clone(&self.service);
+
+        Box::pin(
+            async move {
+                let result = service.call(req).await;
+                let cx = opentelemetry::Context::current();
+                let span = cx.span();
+                let status = match &result {
+                    Ok(response) => response.status(),
+                    Err(e) => e.as_response_error().status_code(),
+                };
+                span.set_attribute(KeyValue::new(
+                    "http.response.status_code",
+                    i64::from(status.as_u16()),
+                ));
+                if status.is_server_error() {
+                    span.set_status(Status::error(status.to_string()));
+                }
+                span.end();
+
+                result.map(|mut response| {
+                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                        response
+                            .headers_mut()
+                            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+                    }
+                    response
+                })
+            }
+            .with_context(cx),
+        )
+    }
+}
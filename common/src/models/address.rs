@@ -1,10 +1,13 @@
+use crate::models::schema::{DateTimeSchema, ObjectIdSchema};
 use mongodb::bson::DateTime;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Address {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
     pub street: String,
     pub city: String,
@@ -12,11 +15,13 @@ pub struct Address {
     pub zip: String,
     pub country: String,
     pub iso_code: String,
+    #[schema(value_type = DateTimeSchema)]
     pub created_at: DateTime,
+    #[schema(value_type = DateTimeSchema)]
     pub updated_at: DateTime,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct NewAddressRequest {
     pub street: String,
     pub city: String,
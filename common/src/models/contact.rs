@@ -1,21 +1,26 @@
+use crate::models::schema::{DateTimeSchema, ObjectIdSchema};
 use mongodb::bson::DateTime;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Contact {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
     pub title: String,
     pub name: String,
     pub email: String,
     pub phone: String,
     pub mobile: String,
+    #[schema(value_type = DateTimeSchema)]
     pub created_at: DateTime,
+    #[schema(value_type = DateTimeSchema)]
     pub updated_at: DateTime,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct NewContactRequest {
     pub title: String,
     pub name: String,
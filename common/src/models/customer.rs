@@ -0,0 +1,98 @@
+use crate::models::address::Address;
+use crate::models::contact::Contact;
+use crate::models::schema::{DateTimeSchema, ObjectIdSchema};
+use crate::models::tenant::default_tenant;
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Customer {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
+    pub primary_phone: String,
+    pub name: String,
+    pub iso_country_code: String,
+    /// Empty when read with a projection omitting addresses and contacts
+    #[serde(default)]
+    pub addresses: Vec<Address>,
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
+    #[schema(value_type = DateTimeSchema)]
+    pub created_at: DateTime,
+    #[schema(value_type = DateTimeSchema)]
+    pub updated_at: DateTime,
+    /// Incremented by every change, returned as the customer's ETag
+    #[serde(default)]
+    pub version: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct NewCustomerRequest {
+    pub name: String,
+    pub primary_phone: String,
+    pub iso_country_code: String,
+}
+
+/// Replaces the editable fields of a customer
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct UpdateCustomerRequest {
+    pub name: String,
+    pub primary_phone: String,
+    pub iso_country_code: String,
+}
+
+/// JSON Merge Patch of a customer, absent fields are left unchanged.
+/// A field set to null asks to remove it, which the required fields refuse.
+#[derive(Deserialize, Debug, Default, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CustomerPatch {
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub primary_phone: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>)]
+    pub iso_country_code: Option<Option<String>>,
+}
+
+/// Keeps a null apart from an absent field, which `#[serde(default)]` leaves as None
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl Customer {
+    pub fn create_new(tenant_id: &str, request: NewCustomerRequest) -> Self {
+        let now = DateTime::now();
+        Customer {
+            id: None,
+            tenant_id: tenant_id.to_string(),
+            primary_phone: request.primary_phone,
+            name: request.name,
+            iso_country_code: request.iso_country_code,
+            addresses: Vec::new(),
+            contacts: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            version: 1,
+        }
+    }
+
+    pub fn add_address(&mut self, address: Address) {
+        self.addresses.push(address);
+    }
+
+    pub fn add_contact(&mut self, contact: Contact) {
+        self.contacts.push(contact);
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+/// Implementation of DependencyHealth struct, the outcome of checking one dependency
+pub struct DependencyHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+/// Implementation of HealthReport struct, used for the readiness response,
+/// the service is only up when every dependency is up
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<DependencyHealth>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<DependencyHealth>) -> Self {
+        let status = if checks.iter().all(|check| check.status == HealthStatus::Up) {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+        HealthReport { status, checks }
+    }
+}
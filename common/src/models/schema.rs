@@ -0,0 +1,29 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// OpenAPI schema of a BSON ObjectId as it is serialized to JSON, `{"$oid": "<hex>"}`,
+/// use it with `#[schema(value_type = ObjectId)]` on ObjectId fields
+#[derive(Serialize, ToSchema)]
+#[schema(as = ObjectId)]
+pub struct ObjectIdSchema {
+    #[serde(rename = "$oid")]
+    #[schema(example = "686153021f8d0d5b45e82113")]
+    pub oid: String,
+}
+
+/// OpenAPI schema of a BSON DateTime as it is serialized to JSON,
+/// `{"$date": {"$numberLong": "<milliseconds since epoch>"}}`
+#[derive(Serialize, ToSchema)]
+#[schema(as = DateTime)]
+pub struct DateTimeSchema {
+    #[serde(rename = "$date")]
+    pub date: NumberLongSchema,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = NumberLong)]
+pub struct NumberLongSchema {
+    #[serde(rename = "$numberLong")]
+    #[schema(example = "1751208706000")]
+    pub number_long: String,
+}
@@ -0,0 +1,18 @@
+use mongodb::bson::{Bson, bson};
+
+/// Tenant assigned to records and tokens created before tenants were introduced
+pub const DEFAULT_TENANT: &str = "default";
+
+pub fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+/// Matches documents of a tenant, documents created before tenants were
+/// introduced have no tenant_id and belong to the default tenant.
+pub fn tenant_filter(tenant_id: &str) -> Bson {
+    if tenant_id == DEFAULT_TENANT {
+        bson!({ "$in": [DEFAULT_TENANT, Bson::Null] })
+    } else {
+        Bson::from(tenant_id)
+    }
+}
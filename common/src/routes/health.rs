@@ -0,0 +1,32 @@
+use actix_web::{HttpResponse, Responder, get, web};
+use serde_json::json;
+
+use crate::models::health::{HealthReport, HealthStatus};
+use crate::services::health::HealthService;
+
+#[utoipa::path(
+    tag = "health",
+    responses((status = 200, description = "The process is running", example = json!({ "status": "up" })))
+)]
+#[get("/health/live")]
+/// Liveness, the process is running and serving HTTP, dependencies are not checked
+async fn live() -> impl Responder {
+    HttpResponse::Ok().json(json!({ "status": HealthStatus::Up }))
+}
+
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Every dependency is up", body = HealthReport),
+        (status = 503, description = "At least one dependency is down", body = HealthReport)
+    )
+)]
+#[get("/health/ready")]
+/// Readiness, reports each dependency with its latency, 503 when any is down
+async fn ready(health_service: web::Data<HealthService>) -> impl Responder {
+    let report = health_service.readiness().await;
+    match report.status {
+        HealthStatus::Up => HttpResponse::Ok().json(report),
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(report),
+    }
+}
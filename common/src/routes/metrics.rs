@@ -0,0 +1,19 @@
+use actix_web::{HttpResponse, Responder, get};
+use prometheus::{Encoder, TextEncoder};
+
+#[utoipa::path(
+    tag = "metrics",
+    responses((status = 200, description = "Prometheus text exposition", content_type = "text/plain", body = String))
+)]
+#[get("/metrics")]
+/// Prometheus metrics in the text exposition format
+async fn metrics() -> impl Responder {
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    match encoder.encode(&prometheus::gather(), &mut body) {
+        Ok(()) => HttpResponse::Ok()
+            .content_type(encoder.format_type())
+            .body(body),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,334 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{HttpResponse, ResponseError};
+use log::{debug, info};
+
+use crate::middleware::authentication::AuthError;
+use crate::models::tenant::default_tenant;
+use crate::utils::load_settings::JwtSettings;
+
+/// Asymmetric algorithms tokens can be signed with, HMAC would need a shared secret
+pub const SUPPORTED_ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Impersonation tokens are short lived, 15 minutes
+const IMPERSONATION_TTL_SECS: usize = 15 * 60;
+
+/// The party acting on behalf of the token subject (RFC 8693 `act` claim)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Actor {
+    pub sub: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    pub iat: usize,
+    // Add other claims as needed
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
+    pub email: Option<String>,
+    pub scopes: Vec<String>,
+    /// Set when an admin is impersonating the subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+}
+
+impl Claims {
+    /// True when the token was issued to an admin impersonating the subject
+    pub fn is_impersonated(&self) -> bool {
+        self.act.is_some()
+    }
+}
+
+// DER encoded object identifiers of the key types and curves
+const RSA_ENCRYPTION_OID: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01,
+];
+const P256_OID: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const P384_OID: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x22];
+const ED25519_OID: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// Type of key a PEM file holds, read from the algorithm identifier in the key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyKind {
+    Rsa,
+    EcP256,
+    EcP384,
+    Ed25519,
+}
+
+impl KeyKind {
+    /// Reads the type of a PEM encoded public or private key
+    pub fn from_pem(pem: &[u8]) -> Result<Self, String> {
+        let not_pem = || "Key is not PEM encoded".to_string();
+        let pem = std::str::from_utf8(pem).map_err(|_| not_pem())?;
+        let mut lines = pem
+            .lines()
+            .map(str::trim)
+            .skip_while(|line| !line.starts_with("-----BEGIN "));
+        // PKCS#1 keys name the type in the label instead of an identifier
+        if lines.next().ok_or_else(not_pem)?.contains(" RSA ") {
+            return Ok(KeyKind::Rsa);
+        }
+        let body: String = lines
+            .take_while(|line| !line.starts_with("-----END "))
+            .collect();
+        let der = STANDARD.decode(body).map_err(|_| not_pem())?;
+
+        let contains = |oid: &[u8]| der.windows(oid.len()).any(|window| window == oid);
+        if contains(RSA_ENCRYPTION_OID) {
+            Ok(KeyKind::Rsa)
+        } else if contains(P256_OID) {
+            Ok(KeyKind::EcP256)
+        } else if contains(P384_OID) {
+            Ok(KeyKind::EcP384)
+        } else if contains(ED25519_OID) {
+            Ok(KeyKind::Ed25519)
+        } else {
+            Err("Unsupported key type, expected an RSA, P-256, P-384 or Ed25519 key".to_string())
+        }
+    }
+
+    /// Whether tokens signed with `algorithm` use this type of key
+    pub fn supports(self, algorithm: Algorithm) -> bool {
+        matches!(
+            (self, algorithm),
+            (
+                KeyKind::Rsa,
+                Algorithm::RS256
+                    | Algorithm::RS384
+                    | Algorithm::RS512
+                    | Algorithm::PS256
+                    | Algorithm::PS384
+                    | Algorithm::PS512
+            ) | (KeyKind::EcP256, Algorithm::ES256)
+                | (KeyKind::EcP384, Algorithm::ES384)
+                | (KeyKind::Ed25519, Algorithm::EdDSA)
+        )
+    }
+}
+
+impl fmt::Display for KeyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyKind::Rsa => write!(f, "RSA"),
+            KeyKind::EcP256 => write!(f, "EC P-256"),
+            KeyKind::EcP384 => write!(f, "EC P-384"),
+            KeyKind::Ed25519 => write!(f, "Ed25519"),
+        }
+    }
+}
+
+/// Fails with the key path and both types when the key does not fit the
+/// algorithm, rather than with the parser's error for the wrong key type
+fn check_key_kind(path: &str, pem: &[u8], algorithm: Algorithm) -> Result<(), String> {
+    let kind = KeyKind::from_pem(pem).map_err(|e| format!("{}: {}", path, e))?;
+    if !kind.supports(algorithm) {
+        return Err(format!(
+            "{} holds an {} key, which cannot be used with {:?}, check jwt.algorithm",
+            path, kind, algorithm
+        ));
+    }
+    Ok(())
+}
+
+pub struct CertificateService {
+    algorithm: Algorithm,
+    /// Only present for services that issue tokens
+    encoding_key: Option<EncodingKey>,
+    decoding_key: DecodingKey,
+}
+
+/// Creates a new instance of the CertificateService
+impl CertificateService {
+    /// Creates a CertificateService that only verifies RS256 tokens
+    pub fn new(public_key_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_algorithm(public_key_path, Algorithm::RS256)
+    }
+
+    /// Creates a verifying CertificateService from the configured public key and algorithm
+    pub fn from_settings(settings: &JwtSettings) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_algorithm(&settings.public_key_path, settings.algorithm)
+    }
+
+    /// Creates a signing CertificateService from the configured key pair and algorithm
+    pub fn signing_from_settings(
+        settings: &JwtSettings,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_signing_key(
+            &settings.private_key_path,
+            &settings.public_key_path,
+            settings.algorithm,
+        )
+    }
+
+    /// Creates a CertificateService that only verifies tokens with a PEM public key,
+    /// RSA (RS*/PS*), ECDSA (ES256/ES384) and EdDSA (Ed25519) keys are supported.
+    /// A key whose type does not fit the algorithm is refused with both named.
+    pub fn with_algorithm(
+        public_key_path: &str,
+        algorithm: Algorithm,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if !SUPPORTED_ALGORITHMS.contains(&algorithm) {
+            return Err(format!("Unsupported key algorithm {:?}", algorithm).into());
+        }
+        let public_key = fs::read(public_key_path)?;
+        check_key_kind(public_key_path, &public_key, algorithm)?;
+
+        let decoding_key = match algorithm {
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => DecodingKey::from_rsa_pem(&public_key)?,
+            Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(&public_key)?,
+            Algorithm::EdDSA => DecodingKey::from_ed_pem(&public_key)?,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                unreachable!("HMAC is not in SUPPORTED_ALGORITHMS")
+            }
+        };
+
+        Ok(CertificateService {
+            algorithm,
+            encoding_key: None,
+            decoding_key,
+        })
+    }
+
+    /// Creates a CertificateService that signs and verifies tokens with a PEM key pair
+    pub fn with_signing_key(
+        private_key_path: &str,
+        public_key_path: &str,
+        algorithm: Algorithm,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut service = Self::with_algorithm(public_key_path, algorithm)?;
+        let private_key = fs::read(private_key_path)?;
+        check_key_kind(private_key_path, &private_key, algorithm)?;
+
+        let encoding_key = match algorithm {
+            Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(&private_key)?,
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&private_key)?,
+            _ => EncodingKey::from_rsa_pem(&private_key)?,
+        };
+        service.encoding_key = Some(encoding_key);
+
+        Ok(service)
+    }
+
+    fn encoding_key(&self) -> Result<&EncodingKey, jsonwebtoken::errors::Error> {
+        self.encoding_key
+            .as_ref()
+            .ok_or_else(|| jsonwebtoken::errors::ErrorKind::InvalidKeyFormat.into())
+    }
+
+    /// The algorithm tokens are signed and verified with
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Creates a new JWT token for a user
+    pub fn create_token(
+        &self,
+        user_id: &str,
+        tenant_id: &str,
+        email: Option<String>,
+        scopes: Vec<String>,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as usize;
+
+        let claims = Claims {
+            sub: user_id.to_string(),
+            exp: now + 3600, // Token expires in 1 hour
+            iat: now,
+            tenant_id: tenant_id.to_string(),
+            email,
+            scopes,
+            act: None,
+        };
+
+        let header = Header::new(self.algorithm);
+        encode(&header, &claims, self.encoding_key()?)
+    }
+
+    /// Creates a short lived JWT token for a user, carrying the admin
+    /// performing the impersonation in the `act` claim
+    pub fn create_impersonation_token(
+        &self,
+        user_id: &str,
+        tenant_id: &str,
+        email: Option<String>,
+        scopes: Vec<String>,
+        actor: Actor,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as usize;
+
+        let claims = Claims {
+            sub: user_id.to_string(),
+            exp: now + IMPERSONATION_TTL_SECS,
+            iat: now,
+            tenant_id: tenant_id.to_string(),
+            email,
+            scopes,
+            act: Some(actor),
+        };
+
+        let header = Header::new(self.algorithm);
+        encode(&header, &claims, self.encoding_key()?)
+    }
+
+    /// Verifies a JWT token and returns the claims, only the algorithm of
+    /// the configured key is accepted regardless of the token header.
+    pub fn verify_token(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let validation = Validation::new(self.algorithm);
+        let token_data = decode::<Claims>(token, &self.decoding_key, &validation)?;
+        Ok(token_data.claims)
+    }
+
+    /// Checks if a JWT token is valid and has a specific scope, extension
+    /// to ensure the token is valid and has the required scope.
+    /// Returns the verified claims so callers can scope work to the tenant.
+    pub fn authorize(&self, token: &str, required_scope: &str) -> Result<Claims, AuthError> {
+        debug!("Verifying scope {}", required_scope);
+        let claims = self
+            .verify_token(token)
+            .map_err(|_| AuthError::InvalidToken)?;
+        if !claims.scopes.iter().any(|scope| scope == required_scope) {
+            return Err(AuthError::InsufficientScope(required_scope.to_string()));
+        }
+        if let Some(actor) = &claims.act {
+            info!("{} is impersonating {}", actor.sub, claims.sub);
+        }
+        Ok(claims)
+    }
+
+    /// Same as `authorize`, with the failure already rendered as a 401 or 403 response
+    pub fn has_scope(&self, token: &str, required_scope: &str) -> Result<Claims, HttpResponse> {
+        self.authorize(token, required_scope)
+            .map_err(|e| e.error_response())
+    }
+}
@@ -0,0 +1,74 @@
+use mongodb::Database;
+use mongodb::bson::doc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::messaging::bus::MessageBus;
+use crate::models::health::{DependencyHealth, HealthReport, HealthStatus};
+
+/// A dependency that does not answer within this time is reported as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Checks the dependencies a service needs to serve requests,
+/// services only pass the dependencies they actually use
+pub struct HealthService {
+    database: Option<Database>,
+    message_bus: Option<Arc<dyn MessageBus>>,
+}
+
+impl HealthService {
+    pub fn new(database: Option<Database>, message_bus: Option<Arc<dyn MessageBus>>) -> Self {
+        HealthService {
+            database,
+            message_bus,
+        }
+    }
+
+    /// Checks every dependency, readiness fails when any of them is down
+    pub async fn readiness(&self) -> HealthReport {
+        let mut checks = Vec::new();
+        if let Some(database) = &self.database {
+            checks.push(check_database(database).await);
+        }
+        if let Some(message_bus) = &self.message_bus {
+            checks.push(check_message_bus(message_bus.as_ref()));
+        }
+        HealthReport::new(checks)
+    }
+}
+
+/// Pings MongoDB, the round trip time is reported as the latency
+async fn check_database(database: &Database) -> DependencyHealth {
+    let started = Instant::now();
+    let error =
+        match tokio::time::timeout(CHECK_TIMEOUT, database.run_command(doc! {"ping": 1})).await {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some(format!("No response within {:?}", CHECK_TIMEOUT)),
+        };
+    dependency_health("mongodb", started, error)
+}
+
+/// Checks the message bus is still connected, the state is tracked locally
+fn check_message_bus(message_bus: &dyn MessageBus) -> DependencyHealth {
+    let started = Instant::now();
+    let error = if message_bus.is_connected() {
+        None
+    } else {
+        Some("Message bus is not connected".to_string())
+    };
+    dependency_health("rabbitmq", started, error)
+}
+
+fn dependency_health(name: &str, started: Instant, error: Option<String>) -> DependencyHealth {
+    DependencyHealth {
+        name: name.to_string(),
+        status: if error.is_none() {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        },
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
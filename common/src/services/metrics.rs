@@ -0,0 +1,106 @@
+use opentelemetry::context::FutureExt;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue, global};
+use prometheus::{HistogramVec, IntCounterVec, register_histogram_vec, register_int_counter_vec};
+use std::fmt::Display;
+use std::future::IntoFuture;
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use crate::utils::telemetry::TRACER_NAME;
+
+/// Metrics are registered with the prometheus default registry on first use
+/// and served by the `/metrics` route.
+pub static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests by route pattern, method and status",
+        &["method", "route", "status"]
+    )
+    .expect("Failed to register http_requests_total")
+});
+
+pub static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "HTTP request latency by route pattern, method and status",
+        &["method", "route", "status"]
+    )
+    .expect("Failed to register http_request_duration_seconds")
+});
+
+pub static MONGO_OPERATION_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "mongo_operation_duration_seconds",
+        "MongoDB operation latency by collection, operation and result",
+        &["collection", "operation", "result"]
+    )
+    .expect("Failed to register mongo_operation_duration_seconds")
+});
+
+pub static AMQP_PUBLISHED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "amqp_published_total",
+        "AMQP messages published by routing key and result",
+        &["routing_key", "result"]
+    )
+    .expect("Failed to register amqp_published_total")
+});
+
+pub static AMQP_CONSUMED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "amqp_consumed_total",
+        "AMQP deliveries consumed by queue and result",
+        &["queue", "result"]
+    )
+    .expect("Failed to register amqp_consumed_total")
+});
+
+/// Label value for the outcome of an operation
+pub fn result_label<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() { "ok" } else { "error" }
+}
+
+/// Awaits a MongoDB action, recording how long it took and a client span
+/// in the current trace, use it around collection calls in the service layer
+pub async fn time_mongo<A, T, E>(collection: &str, operation: &str, action: A) -> Result<T, E>
+where
+    A: IntoFuture<Output = Result<T, E>>,
+    E: Display,
+{
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder(format!("{} {}", operation, collection))
+        .with_kind(SpanKind::Client)
+        .with_attributes([
+            KeyValue::new("db.system.name", "mongodb"),
+            KeyValue::new("db.collection.name", collection.to_string()),
+            KeyValue::new("db.operation.name", operation.to_string()),
+        ])
+        .start(&tracer);
+    let cx = Context::current_with_span(span);
+
+    let started = Instant::now();
+    let result = action.into_future().with_context(cx.clone()).await;
+    MONGO_OPERATION_DURATION
+        .with_label_values(&[collection, operation, result_label(&result)])
+        .observe(started.elapsed().as_secs_f64());
+
+    if let Err(e) = &result {
+        cx.span().set_status(Status::error(e.to_string()));
+    }
+    cx.span().end();
+    result
+}
+
+pub fn record_published<T, E>(routing_key: &str, result: &Result<T, E>) {
+    AMQP_PUBLISHED
+        .with_label_values(&[routing_key, result_label(result)])
+        .inc();
+}
+
+pub fn record_consumed<T, E>(queue: &str, result: &Result<T, E>) {
+    AMQP_CONSUMED
+        .with_label_values(&[queue, result_label(result)])
+        .inc();
+}
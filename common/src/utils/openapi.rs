@@ -0,0 +1,27 @@
+use utoipa::Modify;
+use utoipa::openapi::OpenApi;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+/// Name of the security scheme referenced by `security(...)` on protected operations
+pub const BEARER_AUTH: &str = "bearer_auth";
+
+/// Registers the JWT bearer security scheme issued by authentication_api
+pub struct BearerSecurity;
+
+impl Modify for BearerSecurity {
+    fn modify(&self, openapi: &mut OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "Token from /auth/login, the scopes listed on an operation must be in its scopes claim",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::utils::load_settings::PasswordSettings;
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        Error as PasswordHashError, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::OsRng,
+    },
+};
+use std::fmt;
+
+/// Errors raised while hashing or verifying a password
+#[derive(Debug)]
+pub enum PasswordError {
+    Argon2(PasswordHashError),
+    Bcrypt(bcrypt::BcryptError),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::Argon2(e) => write!(f, "{}", e),
+            PasswordError::Bcrypt(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+impl From<PasswordHashError> for PasswordError {
+    fn from(e: PasswordHashError) -> Self {
+        PasswordError::Argon2(e)
+    }
+}
+
+impl From<bcrypt::BcryptError> for PasswordError {
+    fn from(e: bcrypt::BcryptError) -> Self {
+        PasswordError::Bcrypt(e)
+    }
+}
+
+/// Stored in place of a hash for users imported without a password, they
+/// cannot log in until a password is set
+pub const NO_PASSWORD: &str = "";
+
+/// False for users without a password, see [`NO_PASSWORD`]
+pub fn has_password(hash: &str) -> bool {
+    hash != NO_PASSWORD
+}
+
+/// Builds an Argon2id hasher with the configured cost parameters.
+fn argon2(settings: &PasswordSettings) -> Result<Argon2<'static>, PasswordHashError> {
+    let params = Params::new(
+        settings.memory_kib,
+        settings.iterations,
+        settings.parallelism,
+        None,
+    )?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Legacy hashes imported from the old system use bcrypt ($2a$, $2b$, $2y$).
+/// The broken $2x$ variant is not accepted.
+fn is_bcrypt_hash(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+/// Hashes a password using Argon2id with the configured parameters.
+pub fn hash_password(password: &str, settings: &PasswordSettings) -> Result<String, PasswordError> {
+    let salt = SaltString::generate(&mut OsRng);
+    let argon2 = argon2(settings)?;
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string();
+    Ok(hash)
+}
+
+/// True when the hash can be verified, an Argon2 PHC string or a bcrypt hash.
+pub fn is_supported_hash(hash: &str) -> bool {
+    if is_bcrypt_hash(hash) {
+        return hash.len() == 60;
+    }
+    PasswordHash::new(hash).is_ok_and(|parsed_hash| {
+        [Algorithm::Argon2id, Algorithm::Argon2i, Algorithm::Argon2d]
+            .iter()
+            .any(|algorithm| parsed_hash.algorithm == algorithm.ident())
+    })
+}
+
+/// Verifies a password against an Argon2 PHC string or a legacy bcrypt hash.
+/// Users imported without a password never match.
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    if !has_password(hash) {
+        return Ok(false);
+    }
+    if is_bcrypt_hash(hash) {
+        return Ok(bcrypt::verify(password, hash)?);
+    }
+
+    let parsed_hash = PasswordHash::new(hash)?;
+    let argon2 = Argon2::default();
+    match argon2.verify_password(password.as_bytes(), &parsed_hash) {
+        Ok(()) => Ok(true),
+        Err(PasswordHashError::Password) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// True when the hash should be replaced after a successful login: legacy
+/// bcrypt hashes, other Argon2 variants and hashes weaker than the
+/// configured parameters.
+pub fn needs_rehash(hash: &str, settings: &PasswordSettings) -> bool {
+    if is_bcrypt_hash(hash) {
+        return true;
+    }
+
+    let Ok(parsed_hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    if parsed_hash.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+    match Params::try_from(&parsed_hash) {
+        Ok(params) => {
+            params.m_cost() < settings.memory_kib
+                || params.t_cost() < settings.iterations
+                || params.p_cost() < settings.parallelism
+        }
+        Err(_) => true,
+    }
+}
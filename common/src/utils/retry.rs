@@ -0,0 +1,36 @@
+use crate::utils::load_settings::StartupSettings;
+use log::warn;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Retries connecting to a dependency with exponential backoff,
+/// returns the last error once `max_attempts` is reached.
+pub async fn retry_with_backoff<T, E, F, Fut>(
+    dependency: &str,
+    settings: &StartupSettings,
+    mut connect: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let max_backoff = Duration::from_millis(settings.max_backoff_ms);
+    let mut backoff = Duration::from_millis(settings.initial_backoff_ms);
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < settings.max_attempts => {
+                warn!(
+                    "{} unavailable (attempt {}/{}): {}, retrying in {:?}",
+                    dependency, attempt, settings.max_attempts, e, backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use actix_web::dev::ServerHandle;
+use log::info;
+
+/// Resolves once SIGINT (Ctrl+C) or SIGTERM is received
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("SIGINT received, shutting down"),
+        _ = terminate => info!("SIGTERM received, shutting down"),
+    }
+}
+
+/// Stops the HTTP server gracefully on a shutdown signal, new connections are
+/// refused while in-flight requests are drained. Servers using this must be
+/// built with `disable_signals()` so the signal is only handled here.
+pub fn stop_server_on_signal(handle: ServerHandle) {
+    actix_web::rt::spawn(async move {
+        shutdown_signal().await;
+        handle.stop(true).await;
+    });
+}
@@ -0,0 +1,84 @@
+use actix_web::http::header::HeaderMap;
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::{Context, global};
+use opentelemetry_otlp::{ExporterBuildError, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+
+use crate::utils::load_settings::{TraceExporter, TracingSettings};
+
+/// Name of the tracer spans are created with
+pub const TRACER_NAME: &str = "rust_micro_services";
+
+/// Header carrying the request id over HTTP and AMQP
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Id of the request that started the trace, carried as a value of the
+/// OpenTelemetry context so it follows the trace into published messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+/// Installs the global tracer provider and W3C trace context propagator,
+/// shut the returned provider down on exit to flush pending spans
+pub fn init_tracer(
+    settings: &TracingSettings,
+    service_name: &str,
+) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let resource = Resource::builder()
+        .with_service_name(service_name.to_string())
+        .build();
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    let provider = match settings.exporter {
+        TraceExporter::None => builder.build(),
+        TraceExporter::Stdout => builder
+            .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+            .build(),
+        TraceExporter::Otlp => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(&settings.otlp_endpoint)
+                .build()?;
+            builder.with_batch_exporter(exporter).build()
+        }
+    };
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Continues the trace of an incoming HTTP request, a new trace is started
+/// when the caller sent no `traceparent` header
+pub fn extract_http_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// Writes the trace context and request id as string headers
+pub fn inject_context(cx: &Context, injector: &mut dyn Injector) {
+    global::get_text_map_propagator(|propagator| propagator.inject_context(cx, injector));
+    if let Some(RequestId(request_id)) = cx.get::<RequestId>() {
+        injector.set(REQUEST_ID_HEADER, request_id.clone());
+    }
+}
+
+/// Reads the trace context and request id written by `inject_context`
+pub fn extract_context(extractor: &dyn Extractor) -> Context {
+    let cx = global::get_text_map_propagator(|propagator| propagator.extract(extractor));
+    match extractor.get(REQUEST_ID_HEADER) {
+        Some(request_id) => cx.with_value(RequestId(request_id.to_string())),
+        None => cx,
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
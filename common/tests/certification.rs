@@ -0,0 +1,207 @@
+use common::services::certification::{Actor, CertificateService, KeyKind, SUPPORTED_ALGORITHMS};
+use common::utils::load_settings::Settings;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use serde_json::json;
+use std::fs;
+
+/// Algorithms with the fixture key pair they use, RSA keys serve RS* and PS*
+const ALGORITHMS: [(Algorithm, &str); 5] = [
+    (Algorithm::RS256, "rs256"),
+    (Algorithm::PS256, "rs256"),
+    (Algorithm::ES256, "es256"),
+    (Algorithm::ES384, "es384"),
+    (Algorithm::EdDSA, "eddsa"),
+];
+
+fn key_path(name: &str, kind: &str) -> String {
+    format!(
+        "{}/tests/fixtures/{}_{}_key.pem",
+        env!("CARGO_MANIFEST_DIR"),
+        name,
+        kind
+    )
+}
+
+fn signing_service(algorithm: Algorithm, name: &str) -> CertificateService {
+    CertificateService::with_signing_key(
+        &key_path(name, "private"),
+        &key_path(name, "public"),
+        algorithm,
+    )
+    .unwrap()
+}
+
+fn scopes() -> Vec<String> {
+    vec!["customer:read".to_string()]
+}
+
+#[test]
+fn tokens_round_trip_with_each_algorithm() {
+    for (algorithm, name) in ALGORITHMS {
+        let service = signing_service(algorithm, name);
+        let token = service
+            .create_token("user-1", "acme", Some("a@acme.com".to_string()), scopes())
+            .unwrap();
+
+        let claims = service.verify_token(&token).unwrap();
+        assert_eq!(claims.sub, "user-1", "{:?}", algorithm);
+        assert_eq!(claims.tenant_id, "acme");
+        assert_eq!(claims.email.as_deref(), Some("a@acme.com"));
+        assert_eq!(claims.scopes, scopes());
+        assert!(!claims.is_impersonated());
+    }
+}
+
+#[test]
+fn verifying_services_accept_tokens_from_the_matching_signer() {
+    for (algorithm, name) in ALGORITHMS {
+        let signer = signing_service(algorithm, name);
+        let verifier =
+            CertificateService::with_algorithm(&key_path(name, "public"), algorithm).unwrap();
+        let actor = Actor {
+            sub: "admin-1".to_string(),
+            email: None,
+        };
+        let token = signer
+            .create_impersonation_token("user-1", "acme", None, scopes(), actor)
+            .unwrap();
+
+        let claims = verifier.verify_token(&token).unwrap();
+        assert_eq!(claims.act.unwrap().sub, "admin-1", "{:?}", algorithm);
+        assert!(verifier.authorize(&token, "customer:read").is_ok());
+    }
+}
+
+#[test]
+fn tokens_signed_with_another_algorithm_are_rejected() {
+    for (algorithm, name) in ALGORITHMS {
+        let verifier = signing_service(algorithm, name);
+        for (other, other_name) in ALGORITHMS.iter().filter(|(a, _)| *a != algorithm) {
+            let token = signing_service(*other, other_name)
+                .create_token("user-1", "acme", None, scopes())
+                .unwrap();
+
+            let error = verifier.verify_token(&token).unwrap_err();
+            assert_eq!(
+                error.kind(),
+                &ErrorKind::InvalidAlgorithm,
+                "{:?} accepted a {:?} token",
+                algorithm,
+                other
+            );
+        }
+    }
+}
+
+#[test]
+fn hmac_tokens_keyed_with_the_public_key_are_rejected() {
+    for (algorithm, name) in ALGORITHMS {
+        let verifier = signing_service(algorithm, name);
+        let public_key = fs::read(key_path(name, "public")).unwrap();
+        let claims = verifier
+            .verify_token(
+                &verifier
+                    .create_token("user-1", "acme", None, scopes())
+                    .unwrap(),
+            )
+            .unwrap();
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(&public_key),
+        )
+        .unwrap();
+
+        let error = verifier.verify_token(&token).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::InvalidAlgorithm,
+            "{:?}",
+            algorithm
+        );
+    }
+}
+
+#[test]
+fn hmac_algorithms_are_not_supported() {
+    let result = CertificateService::with_algorithm(&key_path("rs256", "public"), Algorithm::HS256);
+    assert!(result.is_err());
+}
+
+fn settings(algorithm: &str) -> Settings {
+    serde_json::from_value(json!({
+        "database": {
+            "local_uri": "mongodb://localhost:27017",
+            "remote_uri": "mongodb://localhost:27017",
+            "database_name": "test"
+        },
+        "jwt": { "algorithm": algorithm }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn configuration_accepts_every_supported_algorithm() {
+    for algorithm in SUPPORTED_ALGORITHMS {
+        let settings = settings(&format!("{:?}", algorithm));
+        assert!(settings.validate().is_ok(), "{:?}", algorithm);
+    }
+    assert!(settings("HS256").validate().is_err());
+}
+
+#[test]
+fn key_kinds_are_read_from_the_pem() {
+    let expected = [
+        ("rs256", KeyKind::Rsa),
+        ("es256", KeyKind::EcP256),
+        ("es384", KeyKind::EcP384),
+        ("eddsa", KeyKind::Ed25519),
+    ];
+    for (name, kind) in expected {
+        for part in ["private", "public"] {
+            let pem = fs::read(key_path(name, part)).unwrap();
+            assert_eq!(KeyKind::from_pem(&pem), Ok(kind), "{} {}", name, part);
+        }
+    }
+    let pkcs1 = b"-----BEGIN RSA PUBLIC KEY-----\nMAA=\n-----END RSA PUBLIC KEY-----\n";
+    assert_eq!(KeyKind::from_pem(pkcs1), Ok(KeyKind::Rsa));
+    assert!(KeyKind::from_pem(b"not a key").is_err());
+}
+
+#[test]
+fn keys_not_fitting_the_algorithm_are_refused_with_a_clear_error() {
+    let mismatches = [
+        ("es256", Algorithm::EdDSA, "EC P-256"),
+        ("es384", Algorithm::ES256, "EC P-384"),
+        ("rs256", Algorithm::ES256, "RSA"),
+        ("eddsa", Algorithm::RS256, "Ed25519"),
+    ];
+    for (name, algorithm, kind) in mismatches {
+        let error = CertificateService::with_algorithm(&key_path(name, "public"), algorithm)
+            .err()
+            .expect("Mismatched key should be refused")
+            .to_string();
+        assert!(
+            error.contains(&format!("holds an {} key", kind))
+                && error.contains(&format!("{:?}", algorithm)),
+            "{}",
+            error
+        );
+    }
+
+    // The private key is checked as well
+    let error = CertificateService::with_signing_key(
+        &key_path("es384", "private"),
+        &key_path("es256", "public"),
+        Algorithm::ES256,
+    )
+    .err()
+    .expect("Mismatched private key should be refused")
+    .to_string();
+    assert!(
+        error.contains("es384_private_key.pem holds an EC P-384 key"),
+        "{}",
+        error
+    );
+}
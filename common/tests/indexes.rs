@@ -0,0 +1,135 @@
+use common::database::indexes::{IndexReport, IndexSpec, compare_indexes};
+use mongodb::IndexModel;
+use mongodb::bson::{Document, doc};
+use mongodb::options::IndexOptions;
+
+fn specs() -> Vec<IndexSpec> {
+    vec![
+        IndexSpec::ascending("users", "tenant_id_email", &["tenant_id", "email"]).unique(),
+        IndexSpec::text("users", "name_text", &["name"]),
+        IndexSpec::ascending("invitations", "token", &["token"]).unique(),
+    ]
+}
+
+/// An index as `listIndexes` returns it
+fn index(name: &str, keys: Document, unique: bool, weights: Option<Document>) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(
+            IndexOptions::builder()
+                .name(name.to_string())
+                .unique(unique.then_some(true))
+                .weights(weights)
+                .build(),
+        )
+        .build()
+}
+
+fn id_index() -> IndexModel {
+    index("_id_", doc! { "_id": 1 }, false, None)
+}
+
+/// MongoDB stores a text index under `_fts`/`_ftsx` with the fields in the weights
+fn text_index(name: &str, field: &str) -> IndexModel {
+    index(
+        name,
+        doc! { "_fts": "text", "_ftsx": 1 },
+        false,
+        Some(doc! { field: 1 }),
+    )
+}
+
+fn names(specs: &[&IndexSpec]) -> Vec<&'static str> {
+    specs.iter().map(|spec| spec.name).collect()
+}
+
+#[test]
+fn indexes_matching_their_specs_are_clean() {
+    let specs = specs();
+    let existing = [
+        id_index(),
+        // Directions come back as doubles from some servers
+        index(
+            "tenant_id_email",
+            doc! { "tenant_id": 1.0, "email": 1i64 },
+            true,
+            None,
+        ),
+        text_index("name_text", "name"),
+    ];
+    let mut report = IndexReport::default();
+
+    let missing = compare_indexes("users", &specs, &existing, &mut report);
+    assert!(missing.is_empty());
+    assert!(report.is_clean(), "{:?}", report);
+}
+
+#[test]
+fn missing_indexes_are_returned_for_creation() {
+    let specs = specs();
+    let mut report = IndexReport::default();
+
+    let missing = compare_indexes("users", &specs, &[id_index()], &mut report);
+    assert_eq!(names(&missing), vec!["tenant_id_email", "name_text"]);
+    assert!(report.is_clean());
+
+    let missing = compare_indexes("invitations", &specs, &[], &mut report);
+    assert_eq!(names(&missing), vec!["token"]);
+}
+
+#[test]
+fn changed_keys_or_options_are_drift() {
+    let specs = specs();
+    let existing = [
+        index(
+            "tenant_id_email",
+            doc! { "tenant_id": 1, "email": 1 },
+            false,
+            None,
+        ),
+        text_index("name_text", "title"),
+    ];
+    let mut report = IndexReport::default();
+
+    let missing = compare_indexes("users", &specs, &existing, &mut report);
+    assert!(missing.is_empty());
+    let drifted: Vec<(&str, &str, &str)> = report
+        .drifted
+        .iter()
+        .map(|drift| {
+            (
+                drift.name.as_str(),
+                drift.expected.as_str(),
+                drift.found.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        drifted,
+        vec![
+            (
+                "tenant_id_email",
+                "{ tenant_id: 1, email: 1 } unique",
+                "{ tenant_id: 1, email: 1 }"
+            ),
+            ("name_text", "{ name: \"text\" }", "{ title: \"text\" }"),
+        ]
+    );
+    assert!(report.undeclared.is_empty());
+}
+
+#[test]
+fn indexes_without_a_spec_are_undeclared() {
+    let specs = specs();
+    let existing = [
+        id_index(),
+        index("email_1", doc! { "email": 1 }, false, None),
+        // Declared, but for another collection
+        index("token", doc! { "token": 1 }, true, None),
+    ];
+    let mut report = IndexReport::default();
+
+    compare_indexes("users", &specs, &existing, &mut report);
+    assert_eq!(report.undeclared, vec!["users.email_1", "users.token"]);
+    assert!(report.drifted.is_empty());
+}
@@ -0,0 +1,95 @@
+use common::messaging::bus::{ExchangeKind, MessageBus, MessagingError};
+use common::messaging::in_memory::InMemoryMessageBus;
+
+async fn bus_with_queues(kind: ExchangeKind, bindings: &[(&str, &str)]) -> InMemoryMessageBus {
+    let bus = InMemoryMessageBus::new();
+    bus.declare_exchange("events", kind).await.unwrap();
+    for (queue, binding_key) in bindings {
+        bus.declare_queue(queue).await.unwrap();
+        bus.bind_queue(queue, "events", binding_key).await.unwrap();
+    }
+    bus
+}
+
+async fn publish(bus: &InMemoryMessageBus, routing_key: &str) {
+    bus.publish("events", routing_key, Vec::new(), Default::default())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn topic_exchanges_match_wildcards_by_word() {
+    let bus = bus_with_queues(
+        ExchangeKind::Topic,
+        &[
+            ("one", "customer.*"),
+            ("many", "customer.#"),
+            ("exact", "customer.created"),
+        ],
+    )
+    .await;
+
+    publish(&bus, "customer.created").await;
+    publish(&bus, "customer.address.added").await;
+    publish(&bus, "customer").await;
+    publish(&bus, "order.created").await;
+
+    assert_eq!(bus.queue_len("one"), 1);
+    assert_eq!(bus.queue_len("many"), 3);
+    assert_eq!(bus.queue_len("exact"), 1);
+}
+
+#[tokio::test]
+async fn direct_and_fanout_exchanges_route_by_kind() {
+    let direct = bus_with_queues(ExchangeKind::Direct, &[("created", "customer.created")]).await;
+    publish(&direct, "customer.created").await;
+    publish(&direct, "customer.updated").await;
+    assert_eq!(direct.queue_len("created"), 1);
+
+    let fanout = bus_with_queues(ExchangeKind::Fanout, &[("a", "x"), ("b", "y")]).await;
+    publish(&fanout, "anything").await;
+    assert_eq!(fanout.queue_len("a"), 1);
+    assert_eq!(fanout.queue_len("b"), 1);
+}
+
+#[tokio::test]
+async fn nacked_messages_are_requeued_or_dropped() {
+    let bus = bus_with_queues(ExchangeKind::Topic, &[("queue", "#")]).await;
+    bus.publish("events", "first", b"1".to_vec(), Default::default())
+        .await
+        .unwrap();
+    bus.publish("events", "second", b"2".to_vec(), Default::default())
+        .await
+        .unwrap();
+
+    let mut subscription = bus.subscribe("queue", "test").await.unwrap();
+    let delivery = subscription.next().await.unwrap().unwrap();
+    assert_eq!(delivery.routing_key, "first");
+    delivery.nack(true).await.unwrap();
+
+    // A requeued message is delivered again before the ones behind it
+    let delivery = subscription.next().await.unwrap().unwrap();
+    assert_eq!(delivery.payload, b"1");
+    delivery.nack(false).await.unwrap();
+
+    let delivery = subscription.next().await.unwrap().unwrap();
+    assert_eq!(delivery.routing_key, "second");
+    delivery.ack().await.unwrap();
+    assert_eq!(bus.queue_len("queue"), 0);
+
+    subscription.cancel().await.unwrap();
+    assert!(subscription.next().await.is_none());
+}
+
+#[tokio::test]
+async fn unknown_exchanges_and_queues_are_errors() {
+    let bus = InMemoryMessageBus::new();
+    let result = bus
+        .publish("missing", "key", Vec::new(), Default::default())
+        .await;
+    assert!(matches!(result, Err(MessagingError::UnknownExchange(_))));
+    assert!(matches!(
+        bus.subscribe("missing", "test").await.err(),
+        Some(MessagingError::UnknownQueue(_))
+    ));
+}
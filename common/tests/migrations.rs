@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use common::database::migrations::{
+    MIGRATIONS_COLLECTION, Migration, MigrationError, MigrationRunner, SetMissingField,
+};
+use common::utils::load_settings::MigrationSettings;
+use futures::TryStreamExt;
+use mongodb::bson::{DateTime, Document, doc, oid::ObjectId};
+use mongodb::{Client, Database};
+use std::time::Duration;
+
+/// Tests that need a server only run when this points at a MongoDB instance,
+/// each of them works in a database of its own and drops it afterwards
+const TEST_URI: &str = "MONGODB_TEST_URI";
+
+/// The client connects lazily, so tests that never query need no server
+async fn database(uri: &str) -> Database {
+    Client::with_uri_str(uri)
+        .await
+        .unwrap()
+        .database(&format!("migrations_{}", ObjectId::new().to_hex()))
+}
+
+async fn test_database() -> Option<Database> {
+    match std::env::var(TEST_URI) {
+        Ok(uri) => Some(database(&uri).await),
+        Err(_) => {
+            eprintln!("{} is not set, skipping", TEST_URI);
+            None
+        }
+    }
+}
+
+fn settings() -> MigrationSettings {
+    MigrationSettings {
+        lock_poll_ms: 20,
+        ..MigrationSettings::default()
+    }
+}
+
+fn set_missing(version: u32, name: &'static str) -> Box<dyn Migration> {
+    Box::new(SetMissingField {
+        version,
+        name,
+        collection: "customers",
+        field: name,
+        value: 1i64.into(),
+    })
+}
+
+fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![set_missing(1, "first"), set_missing(3, "third")]
+}
+
+fn lock_collection(database: &Database) -> mongodb::Collection<Document> {
+    database.collection(MIGRATIONS_COLLECTION)
+}
+
+/// Hands the lock to another run while it is being applied
+struct LosesLock;
+
+#[async_trait]
+impl Migration for LosesLock {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "loses_lock"
+    }
+
+    async fn pending_count(&self, _database: &Database) -> mongodb::error::Result<u64> {
+        Ok(0)
+    }
+
+    async fn up(&self, database: &Database) -> mongodb::error::Result<u64> {
+        lock_collection(database)
+            .update_one(
+                doc! { "_id": "lock" },
+                doc! { "$set": { "owner": "other", "locked_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(0)
+    }
+}
+
+#[tokio::test]
+async fn migrations_must_be_in_increasing_version_order() {
+    let database = database("mongodb://localhost:27017").await;
+    for versions in [[3, 1], [2, 2]] {
+        let migrations = versions
+            .iter()
+            .map(|version| set_missing(*version, "field"))
+            .collect();
+        let result = MigrationRunner::new(database.clone(), migrations, &settings());
+        assert!(
+            matches!(result, Err(MigrationError::InvalidOrder(_))),
+            "{:?}",
+            versions
+        );
+    }
+    assert!(MigrationRunner::new(database, migrations(), &settings()).is_ok());
+}
+
+#[tokio::test]
+async fn reports_list_pending_and_unknown_versions() {
+    let database = database("mongodb://localhost:27017").await;
+    let runner = MigrationRunner::new(database, migrations(), &settings()).unwrap();
+
+    let pending: Vec<u32> = runner.pending(&[]).map(|m| m.version()).collect();
+    assert_eq!(pending, vec![1, 3]);
+    let pending: Vec<u32> = runner.pending(&[1]).map(|m| m.version()).collect();
+    assert_eq!(pending, vec![3]);
+    assert_eq!(runner.pending(&[1, 3]).count(), 0);
+
+    let report = runner.report(&[1, 2, 3, 4], true);
+    assert!(report.dry_run);
+    assert!(report.pending.is_empty());
+    assert_eq!(report.unknown_versions, vec![2, 4]);
+    assert!(runner.report(&[1], false).unknown_versions.is_empty());
+}
+
+#[tokio::test]
+async fn dry_runs_count_documents_and_runs_apply_them_once() {
+    let Some(database) = test_database().await else {
+        return;
+    };
+    let customers = database.collection::<Document>("customers");
+    customers
+        .insert_many([doc! {}, doc! {}, doc! { "first": 1i64 }])
+        .await
+        .unwrap();
+    let runner = MigrationRunner::new(database.clone(), migrations(), &settings()).unwrap();
+
+    let report = runner.dry_run().await.unwrap();
+    let counts: Vec<(u32, u64, bool)> = report
+        .pending
+        .iter()
+        .map(|outcome| (outcome.version, outcome.documents, outcome.applied))
+        .collect();
+    assert_eq!(counts, vec![(1, 2, false), (3, 3, false)]);
+    assert_eq!(
+        customers
+            .count_documents(doc! { "third": 1i64 })
+            .await
+            .unwrap(),
+        0
+    );
+
+    let report = runner.run().await.unwrap();
+    assert_eq!(report.pending.len(), 2);
+    assert!(report.pending.iter().all(|outcome| outcome.applied));
+    assert!(runner.run().await.unwrap().pending.is_empty());
+    assert_eq!(
+        customers
+            .count_documents(doc! { "third": 1i64 })
+            .await
+            .unwrap(),
+        3
+    );
+
+    database.drop().await.unwrap();
+}
+
+#[tokio::test]
+async fn expired_locks_are_taken_over() {
+    let Some(database) = test_database().await else {
+        return;
+    };
+    let locked_at = DateTime::from_millis(DateTime::now().timestamp_millis() - 3_600_000);
+    lock_collection(&database)
+        .insert_one(doc! { "_id": "lock", "owner": "crashed", "locked_at": locked_at })
+        .await
+        .unwrap();
+    let runner = MigrationRunner::new(database.clone(), migrations(), &settings()).unwrap();
+
+    let report = runner.run().await.unwrap();
+    assert_eq!(report.pending.len(), 2);
+    let lock = lock_collection(&database)
+        .find_one(doc! { "_id": "lock" })
+        .await
+        .unwrap();
+    assert!(lock.is_none());
+
+    database.drop().await.unwrap();
+}
+
+#[tokio::test]
+async fn runs_wait_for_a_held_lock() {
+    let Some(database) = test_database().await else {
+        return;
+    };
+    let locks = lock_collection(&database);
+    locks
+        .insert_one(doc! { "_id": "lock", "owner": "other", "locked_at": DateTime::now() })
+        .await
+        .unwrap();
+    let runner = MigrationRunner::new(database.clone(), migrations(), &settings()).unwrap();
+    let run = tokio::spawn(async move { runner.run().await });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!run.is_finished());
+    // The other run applies the first migration and releases the lock
+    locks
+        .insert_one(doc! { "_id": 1i64, "name": "first" })
+        .await
+        .unwrap();
+    locks.delete_one(doc! { "_id": "lock" }).await.unwrap();
+
+    let report = run.await.unwrap().unwrap();
+    let applied: Vec<u32> = report
+        .pending
+        .iter()
+        .map(|outcome| outcome.version)
+        .collect();
+    assert_eq!(applied, vec![3]);
+
+    database.drop().await.unwrap();
+}
+
+#[tokio::test]
+async fn runs_stop_when_the_lock_is_taken_over() {
+    let Some(database) = test_database().await else {
+        return;
+    };
+    let migrations = vec![
+        set_missing(1, "first"),
+        Box::new(LosesLock),
+        set_missing(3, "third"),
+    ];
+    let runner = MigrationRunner::new(database.clone(), migrations, &settings()).unwrap();
+
+    let result = runner.run().await;
+    assert!(matches!(result, Err(MigrationError::Locked(owner)) if owner == "other"));
+    let recorded: Vec<Document> = lock_collection(&database)
+        .find(doc! { "_id": { "$type": "number" } })
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].get_i64("_id").unwrap(), 1);
+    // The lock is left to the run that took it over
+    let lock = lock_collection(&database)
+        .find_one(doc! { "_id": "lock" })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(lock.get_str("owner").unwrap(), "other");
+
+    database.drop().await.unwrap();
+}
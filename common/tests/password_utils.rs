@@ -0,0 +1,47 @@
+use common::utils::load_settings::PasswordSettings;
+use common::utils::password_utils::{
+    NO_PASSWORD, hash_password, is_supported_hash, needs_rehash, verify_password,
+};
+
+const PASSWORD: &str = "correct horse battery staple";
+/// A bcrypt hash with the $2y$ prefix PHP's password_hash writes, at the minimum cost
+const LEGACY_2Y_HASH: &str = "$2y$04$.dB66AJywn48.3BXptVjU.XQzJMYdy.V8YxJ7v.JRrfDftsAZ5NbG";
+
+#[test]
+fn legacy_2y_hashes_verify_and_need_a_rehash() {
+    assert!(is_supported_hash(LEGACY_2Y_HASH));
+    assert!(verify_password(PASSWORD, LEGACY_2Y_HASH).unwrap());
+    assert!(!verify_password("wrong password", LEGACY_2Y_HASH).unwrap());
+    assert!(needs_rehash(LEGACY_2Y_HASH, &PasswordSettings::default()));
+}
+
+#[test]
+fn other_bcrypt_prefixes_are_accepted() {
+    for prefix in ["$2a$", "$2b$"] {
+        let hash = LEGACY_2Y_HASH.replacen("$2y$", prefix, 1);
+        assert!(is_supported_hash(&hash), "{}", prefix);
+        assert!(verify_password(PASSWORD, &hash).unwrap(), "{}", prefix);
+    }
+}
+
+#[test]
+fn broken_2x_hashes_are_not_supported() {
+    let hash = LEGACY_2Y_HASH.replacen("$2y$", "$2x$", 1);
+    assert!(!is_supported_hash(&hash));
+    assert!(verify_password(PASSWORD, &hash).is_err());
+}
+
+#[test]
+fn argon2_hashes_round_trip_without_a_rehash() {
+    let settings = PasswordSettings::default();
+    let hash = hash_password(PASSWORD, &settings).unwrap();
+
+    assert!(is_supported_hash(&hash));
+    assert!(verify_password(PASSWORD, &hash).unwrap());
+    assert!(!needs_rehash(&hash, &settings));
+}
+
+#[test]
+fn users_without_a_password_never_match() {
+    assert!(!verify_password("", NO_PASSWORD).unwrap());
+}
@@ -0,0 +1,30 @@
+use crate::errors::api_error::ApiError;
+use crate::routes::customer::{
+    add_address, add_contact, create_customer, get_customer, list_customers, patch_customer,
+    remove_address, remove_contact, update_address, update_contact, update_customer,
+};
+use actix_web::web::{JsonConfig, QueryConfig, ServiceConfig};
+
+/// Registers the API routes, the CustomerHandler, CertificateService and the
+/// `Recipient<Publish<Customer>>` and `Recipient<Publish<CustomerUpdated>>`
+/// events are sent to must be added as app data. Bodies and query strings that
+/// cannot be read are answered as problem+json validation errors.
+pub fn configure(cfg: &mut ServiceConfig) {
+    let json =
+        JsonConfig::default().error_handler(|e, _| ApiError::Validation(e.to_string()).into());
+    let query =
+        QueryConfig::default().error_handler(|e, _| ApiError::Validation(e.to_string()).into());
+    cfg.app_data(json)
+        .app_data(query)
+        .service(create_customer)
+        .service(get_customer)
+        .service(list_customers)
+        .service(update_customer)
+        .service(patch_customer)
+        .service(add_address)
+        .service(update_address)
+        .service(remove_address)
+        .service(add_contact)
+        .service(update_contact)
+        .service(remove_contact);
+}
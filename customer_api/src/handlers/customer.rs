@@ -1,73 +1,338 @@
-use crate::models::address::Address;
-use crate::models::contact::Contact;
-use crate::models::customer::{Customer, NewCustomerRequest};
-use crate::models::messages::CustomerCreated;
-use crate::services::customer::CustomerService;
-use mongodb::bson::{DateTime, Document};
+use crate::errors::api_error::ApiError;
+use crate::models::customer_query::{
+    Cursor, CustomerFilter, CustomerListItem, CustomerPage, CustomerQuery, DEFAULT_PAGE_SIZE,
+    MAX_PAGE_SIZE, PageRequest,
+};
+use crate::models::messages::FieldChange;
+use crate::repositories::customer::{CustomerRepository, SubDocument, changed_since};
+use common::models::address::{Address, NewAddressRequest};
+use common::models::contact::{Contact, NewContactRequest};
+use common::models::customer::{
+    Customer, CustomerPatch, NewCustomerRequest, UpdateCustomerRequest,
+};
+use mongodb::bson::{DateTime, Document, to_document};
+use std::sync::Arc;
 
 pub struct CustomerHandler {
-    customer_service: CustomerService,
+    customer_repository: Arc<dyn CustomerRepository>,
 }
 
 impl CustomerHandler {
-    pub async fn new() -> Self {
+    pub fn new(customer_repository: Arc<dyn CustomerRepository>) -> Self {
         CustomerHandler {
-            customer_service: CustomerService::new().await,
+            customer_repository,
         }
     }
 
     pub async fn create_customer(
         &self,
+        tenant_id: &str,
         customer_request: NewCustomerRequest,
-    ) -> Result<Customer, String> {
-        let new_customer = Customer::create_new(customer_request);
-        // Remove clone; instead, use a reference or move if possible
-        match self.customer_service.create_customer(new_customer).await {
-            Ok(customer) => Ok(customer),
-            Err(err) => Err(err.to_string()),
-        }
+    ) -> Result<Customer, ApiError> {
+        let new_customer = Customer::create_new(tenant_id, customer_request);
+        self.customer_repository.create_customer(new_customer).await
     }
 
-    pub async fn get_customer(&self, id: &str) -> Result<Customer, String> {
-        match self.customer_service.get_customer(id).await {
-            Ok(customer) => Ok(customer),
-            Err(err) => Err(err.to_string()),
-        }
+    pub async fn get_customer(&self, tenant_id: &str, id: &str) -> Result<Customer, ApiError> {
+        self.customer_repository.get_customer(tenant_id, id).await
     }
 
-    pub async fn add_address(&self, id: &str, address: Address) -> Result<Customer, String> {
-        match self.customer_service.get_customer(id).await {
-            Ok(mut customer) => {
-                let mut addresses = customer.addresses;
-                addresses.push(address);
-                let mut update_doc = Document::new();
-                update_doc.insert("addresses", mongodb::bson::to_bson(&addresses).unwrap());
-                update_doc.insert("updated_at", DateTime::now());
+    /// Lists a page of the tenant's customers, the cursor of the next page is
+    /// only returned when more customers follow
+    pub async fn list_customers(
+        &self,
+        tenant_id: &str,
+        query: CustomerQuery,
+    ) -> Result<CustomerPage, ApiError> {
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(ApiError::Validation(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+        let filter = CustomerFilter {
+            name: query.name.filter(|name| !name.trim().is_empty()),
+            iso_country_code: query.iso_country_code,
+            phone: query.phone,
+            created_from: query
+                .created_from
+                .as_deref()
+                .map(|from| parse_timestamp("created_from", from))
+                .transpose()?,
+            created_to: query
+                .created_to
+                .as_deref()
+                .map(|to| parse_timestamp("created_to", to))
+                .transpose()?,
+        };
+        let page = PageRequest {
+            sort: query.sort,
+            order: query.order,
+            // One more than the page to know whether another page follows
+            limit: limit + 1,
+            after: query
+                .cursor
+                .as_deref()
+                .map(|cursor| Cursor::decode(cursor, query.sort, query.order))
+                .transpose()?,
+            summary: query.summary,
+        };
 
-                match self.customer_service.update_customer(id, update_doc).await {
-                    Ok(customer) => Ok(customer),
-                    Err(err) => Err(err.to_string()),
+        let mut customers = self
+            .customer_repository
+            .list_customers(tenant_id, &filter, &page)
+            .await?;
+        let next_cursor = if customers.len() > limit as usize {
+            customers.truncate(limit as usize);
+            customers
+                .last()
+                .and_then(|last| Cursor::after(last, query.sort, query.order))
+                .map(|cursor| cursor.encode())
+        } else {
+            None
+        };
+        let items = customers
+            .into_iter()
+            .map(|customer| {
+                if query.summary {
+                    CustomerListItem::Summary(customer.into())
+                } else {
+                    CustomerListItem::Customer(customer)
                 }
-            }
-            Err(err) => Err(err.to_string()),
-        }
+            })
+            .collect();
+        Ok(CustomerPage { items, next_cursor })
     }
 
-    pub async fn add_contact(&self, id: &str, contact: Contact) -> Result<Customer, String> {
-        match self.customer_service.get_customer(id).await {
-            Ok(mut customer) => {
-                let mut contacts = customer.contacts;
-                contacts.push(contact);
-                let mut update_doc = Document::new();
-                update_doc.insert("contacts", mongodb::bson::to_bson(&contacts).unwrap());
-                update_doc.insert("updated_at", DateTime::now());
+    /// Replaces the name, phone and country code of the customer at `version`,
+    /// returns the customer with the changed fields, nothing is written when no field changes
+    pub async fn update_customer(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        request: UpdateCustomerRequest,
+    ) -> Result<(Customer, Vec<FieldChange>), ApiError> {
+        validate_customer(&request)?;
+        let customer = self.customer_repository.get_customer(tenant_id, id).await?;
+        if customer.version != version {
+            return Err(changed_since(version));
+        }
+        let changes = customer_changes(&customer, &request);
+        if changes.is_empty() {
+            return Ok((customer, changes));
+        }
 
-                match self.customer_service.update_customer(id, update_doc).await {
-                    Ok(customer) => Ok(customer),
-                    Err(err) => Err(err.to_string()),
-                }
-            }
-            Err(err) => Err(err.to_string()),
+        let mut update_doc = Document::new();
+        for change in &changes {
+            update_doc.insert(change.field.as_str(), change.new.as_str());
         }
+        update_doc.insert("updated_at", DateTime::now());
+        let customer = self
+            .customer_repository
+            .update_customer(tenant_id, id, version, update_doc)
+            .await?;
+        Ok((customer, changes))
+    }
+
+    /// Applies a JSON Merge Patch to the customer's fields, then updates it like `update_customer`
+    pub async fn patch_customer(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        patch: CustomerPatch,
+    ) -> Result<(Customer, Vec<FieldChange>), ApiError> {
+        let customer = self.customer_repository.get_customer(tenant_id, id).await?;
+        let request = UpdateCustomerRequest {
+            name: patched("name", patch.name, customer.name)?,
+            primary_phone: patched("primary_phone", patch.primary_phone, customer.primary_phone)?,
+            iso_country_code: patched(
+                "iso_country_code",
+                patch.iso_country_code,
+                customer.iso_country_code,
+            )?,
+        };
+        self.update_customer(tenant_id, id, version, request).await
+    }
+
+    pub async fn add_address(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        address: Address,
+    ) -> Result<Customer, ApiError> {
+        self.customer_repository
+            .push_sub_document(tenant_id, id, SubDocument::Address, to_document(&address)?)
+            .await
+    }
+
+    pub async fn add_contact(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        contact: Contact,
+    ) -> Result<Customer, ApiError> {
+        self.customer_repository
+            .push_sub_document(tenant_id, id, SubDocument::Contact, to_document(&contact)?)
+            .await
+    }
+
+    /// Replaces the fields of an address of the customer at `version`, its id and creation time are kept
+    pub async fn update_address(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        address_id: &str,
+        request: NewAddressRequest,
+    ) -> Result<Customer, ApiError> {
+        let fields = sub_document_fields(&request)?;
+        self.customer_repository
+            .update_sub_document(
+                tenant_id,
+                id,
+                version,
+                SubDocument::Address,
+                address_id,
+                fields,
+            )
+            .await
+    }
+
+    /// Removes an address of the customer at `version`
+    pub async fn remove_address(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        address_id: &str,
+    ) -> Result<Customer, ApiError> {
+        self.customer_repository
+            .remove_sub_document(tenant_id, id, version, SubDocument::Address, address_id)
+            .await
+    }
+
+    /// Replaces the fields of a contact of the customer at `version`, its id and creation time are kept
+    pub async fn update_contact(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        contact_id: &str,
+        request: NewContactRequest,
+    ) -> Result<Customer, ApiError> {
+        let fields = sub_document_fields(&request)?;
+        self.customer_repository
+            .update_sub_document(
+                tenant_id,
+                id,
+                version,
+                SubDocument::Contact,
+                contact_id,
+                fields,
+            )
+            .await
+    }
+
+    /// Removes a contact of the customer at `version`
+    pub async fn remove_contact(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        contact_id: &str,
+    ) -> Result<Customer, ApiError> {
+        self.customer_repository
+            .remove_sub_document(tenant_id, id, version, SubDocument::Contact, contact_id)
+            .await
     }
 }
+
+fn parse_timestamp(parameter: &str, value: &str) -> Result<DateTime, ApiError> {
+    DateTime::parse_rfc3339_str(value)
+        .map_err(|_| ApiError::Validation(format!("{} must be an RFC 3339 timestamp", parameter)))
+}
+
+/// Fields of an address or contact request to set, with a new `updated_at`
+fn sub_document_fields<T: serde::Serialize>(request: &T) -> Result<Document, ApiError> {
+    let mut fields = to_document(request)?;
+    fields.insert("updated_at", DateTime::now());
+    Ok(fields)
+}
+
+/// Checks every field and reports all problems at once
+fn validate_customer(request: &UpdateCustomerRequest) -> Result<(), ApiError> {
+    let mut errors = Vec::new();
+    if request.name.trim().is_empty() || request.name.chars().count() > 200 {
+        errors.push("name must be between 1 and 200 characters");
+    }
+    let digits = request
+        .primary_phone
+        .chars()
+        .filter(char::is_ascii_digit)
+        .count();
+    if !(4..=20).contains(&digits)
+        || !request
+            .primary_phone
+            .chars()
+            .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+    {
+        errors.push("primary_phone must be a phone number of 4 to 20 digits");
+    }
+    if request.iso_country_code.len() != 2
+        || !request
+            .iso_country_code
+            .chars()
+            .all(|c| c.is_ascii_uppercase())
+    {
+        errors.push("iso_country_code must be two uppercase letters");
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::Validation(errors.join("; ")))
+    }
+}
+
+/// Value of a field after a merge patch, the required fields cannot be removed
+fn patched(
+    field: &str,
+    patch: Option<Option<String>>,
+    current: String,
+) -> Result<String, ApiError> {
+    match patch {
+        None => Ok(current),
+        Some(Some(value)) => Ok(value),
+        Some(None) => Err(ApiError::Validation(format!(
+            "{} is required and cannot be removed",
+            field
+        ))),
+    }
+}
+
+fn customer_changes(customer: &Customer, request: &UpdateCustomerRequest) -> Vec<FieldChange> {
+    [
+        ("name", &customer.name, &request.name),
+        (
+            "primary_phone",
+            &customer.primary_phone,
+            &request.primary_phone,
+        ),
+        (
+            "iso_country_code",
+            &customer.iso_country_code,
+            &request.iso_country_code,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .map(|(field, old, new)| FieldChange {
+        field: field.to_string(),
+        old: old.clone(),
+        new: new.clone(),
+    })
+    .collect()
+}
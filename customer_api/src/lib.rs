@@ -1,27 +1,20 @@
-#![allow(unused)]
+pub mod app;
+pub mod migrations;
 
-pub mod database {
-    pub mod mongo_db;
-}
-
-pub mod utils {
-    pub mod load_settings;
-    pub mod password_utils;
+pub mod errors {
+    pub mod api_error;
 }
 
 pub mod models {
-    pub mod address;
-    pub mod contact;
-    pub mod customer;
+    pub mod customer_query;
     pub mod messages;
 }
-pub mod services {
-    pub mod certification;
+pub mod repositories {
     pub mod customer;
+    pub mod in_memory;
 }
 
 pub mod messaging {
-    pub mod connection;
     pub mod publisher;
 }
 
@@ -31,4 +24,5 @@ pub mod handlers {
 
 pub mod routes {
     pub mod customer;
+    pub mod openapi;
 }
@@ -1,101 +1,156 @@
 use actix_web::middleware::Logger;
 use actix_web::{App, HttpServer, web};
+use common::database::indexes::ensure_indexes;
+use common::database::migrations::MigrationRunner;
+use common::database::mongo_db::MongoDb;
+use common::messaging::bus::{ExchangeKind, MessageBus};
+use common::messaging::lapin_bus::LapinMessageBus;
+use common::middleware::metrics::RequestMetrics;
+use common::middleware::request_tracing::{LOG_FORMAT, RequestTracing};
+use common::models::customer::Customer;
+use common::routes::health::{live, ready};
+use common::routes::metrics::metrics;
+use common::services::certification::CertificateService;
+use common::services::health::HealthService;
+use common::utils::load_settings::Settings;
+use common::utils::retry::retry_with_backoff;
+use common::utils::shutdown::stop_server_on_signal;
+use common::utils::telemetry::init_tracer;
+use customer_api::app::configure;
 use customer_api::handlers::customer::CustomerHandler;
-use customer_api::messaging::connection::{create_channel, declare_exchange};
-use customer_api::messaging::publisher::Publisher;
-use customer_api::routes::customer::{add_address, add_contact, create_customer};
-use customer_api::services::certification::CertificateService;
-use log::info;
+use customer_api::messaging::publisher::{Publish, Publisher, Shutdown};
+use customer_api::migrations::migrations;
+use customer_api::models::messages::CustomerUpdated;
+use customer_api::repositories::customer::MongoCustomerRepository;
+use customer_api::routes::openapi::ApiDoc;
+use env_logger::Env;
+use log::{info, warn};
+use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use actix::Actor;
-use lapin::ExchangeKind::Direct;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init();
+    let settings = Settings::load().map_err(std::io::Error::other)?;
+    env_logger::Builder::from_env(Env::default().default_filter_or(&settings.logging.level)).init();
+    let tracer_provider =
+        init_tracer(&settings.tracing, "customer_api").map_err(std::io::Error::other)?;
+
+    // `customer_api migrate [--dry-run]` only runs the migrations
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("migrate") {
+        return migrate(&settings, args.any(|arg| arg == "--dry-run")).await;
+    }
 
     info!("Starting Customer API...");
 
-    let channel = create_channel().await.expect("Failed to create channel");
-    declare_exchange(&channel, "customer_exchange", Direct).await;
+    let message_bus: Arc<dyn MessageBus> = Arc::new(
+        retry_with_backoff("RabbitMQ", &settings.startup, || {
+            LapinMessageBus::connect(&settings.messaging)
+        })
+        .await
+        .map_err(std::io::Error::other)?,
+    );
+    // Consumers bind with topic patterns such as "customer.#". RabbitMQ refuses to
+    // redeclare an exchange with another type, so this is not the old direct exchange
+    message_bus
+        .declare_exchange(&settings.messaging.exchange, ExchangeKind::Topic)
+        .await
+        .map_err(std::io::Error::other)?;
+
+    let mongo_db = MongoDb::connect(&settings.database)
+        .await
+        .map_err(std::io::Error::other)?;
+    retry_with_backoff("MongoDB", &settings.startup, || mongo_db.ping())
+        .await
+        .map_err(std::io::Error::other)?;
+    if settings.migrations.run_on_startup {
+        MigrationRunner::new(
+            mongo_db.database.clone(),
+            migrations(),
+            &settings.migrations,
+        )
+        .map_err(std::io::Error::other)?
+        .run()
+        .await
+        .map_err(std::io::Error::other)?;
+    }
+    ensure_indexes(&mongo_db.database, &MongoCustomerRepository::indexes())
+        .await
+        .map_err(std::io::Error::other)?;
+    let health_service =
+        HealthService::new(Some(mongo_db.database.clone()), Some(message_bus.clone()));
 
     let publisher = Publisher {
-        channel,
-        exchange: "customer_exchange".into(),
+        bus: message_bus.clone(),
+        exchange: settings.messaging.exchange.clone(),
     }
     .start();
-    let customer_handler = CustomerHandler::new().await;
-    let cert_handler = CertificateService::new("RSAKeyStore/public_key.pem")
+    let customer_handler =
+        CustomerHandler::new(Arc::new(MongoCustomerRepository::new(&mongo_db.database)));
+    let cert_handler = CertificateService::from_settings(&settings.jwt)
         .expect("Failed to create CertificateService");
     let customer_data = web::Data::new(customer_handler);
     let cert_service = web::Data::new(cert_handler);
 
-    let publisher_service = web::Data::new(publisher);
-    HttpServer::new(move || {
+    let publisher_service = web::Data::new(publisher.clone().recipient::<Publish<Customer>>());
+    let update_publisher_service =
+        web::Data::new(publisher.clone().recipient::<Publish<CustomerUpdated>>());
+    let health_data = web::Data::new(health_service);
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(Logger::default())
+            .wrap(RequestTracing)
+            .wrap(Logger::new(LOG_FORMAT))
+            .wrap(RequestMetrics)
             .app_data(customer_data.clone())
             .app_data(cert_service.clone())
             .app_data(publisher_service.clone())
-            .service(create_customer)
-            .service(add_address)
-            .service(add_contact)
+            .app_data(update_publisher_service.clone())
+            .app_data(health_data.clone())
+            .service(live)
+            .service(ready)
+            .service(metrics)
+            .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/openapi.json", ApiDoc::openapi()))
+            .configure(configure)
     })
-    .bind("127.0.0.1:8090")?
-    .run()
-    .await
-
-    // let user_handler = UserHandler::new().await;
-    // let cert_handler =
-    //     CertificateService::new("RSAKeyStore/private_key.pem", "RSAKeyStore/public_key.pem")
-    //         .expect("Failed to create CertificateService");
-
-    // let handler_data = web::Data::new(auth_handler);
-    // let user_data = web::Data::new(user_handler);
-    // let cert_service = web::Data::new(cert_handler);
-    // println!("Starting Database...");
-
-    // let new_request = NewCustomerRequest {
-    //     name: "Space design house".to_string(),
-    //     primary_phone: "1234567890".to_string(),
-    //     iso_country_code: "US".to_string(),
-    // };
-
-    // let new_address_request = NewAddressRequest {
-    //     street: "123 Main St".to_string(),
-    //     city: "Anytown".to_string(),
-    //     state: "CA".to_string(),
-    //     zip: "12345".to_string(),
-    //     country: "US".to_string(),
-    //     iso_code: "US".to_string(),
-    // };
-
-    // let address = Address::create_new(new_address_request);
-
-    // let customer_id = "686153021f8d0d5b45e82113";
-
-    // let customer_handler = CustomerHandler::new().await;
-
-    // let result = customer_handler.get_customer(customer_id).await;
-
-    // match result {
-    //     Ok(_customer) => {
-    //         println!("Retrieved customer successfully. {:?}", _customer);
-
-    //         let result = customer_handler.add_address(customer_id, address).await;
-
-    //         match result {
-    //             Ok(_customer) => println!("Created customer successfully. {:?}", _customer),
-    //             Err(e) => println!("Failed to create customer: {:?}", e),
-    //         }
-    //     }
-    //     Err(e) => println!("Failed to retrieve customer: {:?}", e),
-    // }
+    .shutdown_timeout(settings.server.shutdown_timeout_secs)
+    .disable_signals()
+    .bind(settings.server.bind_address(8090))?
+    .run();
+    stop_server_on_signal(server.handle());
+    let result = server.await;
+
+    // Requests are drained, publish what is still queued before closing the connection
+    if publisher.send(Shutdown).await.is_err() {
+        warn!("Publisher stopped before flushing");
+    }
+    message_bus.close().await;
 
-    // // let result = customer_handler.create_customer(new_request).await;
+    // Flush spans that are still buffered before exiting
+    if let Err(e) = tracer_provider.shutdown() {
+        warn!("Failed to shut down tracer provider: {}", e);
+    }
+    result
+}
 
-    // // match result {
-    // //     Ok(_customer) => println!("Created customer successfully. {:?}", _customer),
-    // //     Err(e) => println!("Failed to create customer: {:?}", e),
-    // // }
+/// Runs or, with `dry_run`, reports the pending migrations and prints the report as JSON
+async fn migrate(settings: &Settings, dry_run: bool) -> std::io::Result<()> {
+    let mongo_db = MongoDb::connect(&settings.database)
+        .await
+        .map_err(std::io::Error::other)?;
+    let runner = MigrationRunner::new(mongo_db.database, migrations(), &settings.migrations)
+        .map_err(std::io::Error::other)?;
+    let report = if dry_run {
+        runner.dry_run().await
+    } else {
+        runner.run().await
+    }
+    .map_err(std::io::Error::other)?;
+    println!(
+        "{}",
+        serde_json::to_string(&report).map_err(std::io::Error::other)?
+    );
+    Ok(())
 }
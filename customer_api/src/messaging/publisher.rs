@@ -1,19 +1,17 @@
-use crate::messaging::connection::{create_channel, declare_exchange};
-use crate::models::customer::Customer;
-use crate::models::messages::CustomerCreated;
-use lapin::options::ExchangeDeclareOptions;
-use lapin::{
-    BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind, options::*,
-    types::FieldTable,
-};
+use common::messaging::bus::MessageBus;
+use common::messaging::trace_context::trace_headers;
+use common::services::metrics::record_published;
+use common::utils::telemetry::TRACER_NAME;
 
 use actix::prelude::*;
-use log::info;
+use log::{info, warn};
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context as TraceContext, KeyValue, global};
 use serde::Serialize;
-use tokio_amqp::*;
+use std::sync::Arc;
 
 pub struct Publisher {
-    pub channel: Channel,
+    pub bus: Arc<dyn MessageBus>,
     pub exchange: String,
 }
 
@@ -26,36 +24,81 @@ impl Actor for Publisher {
 pub struct Publish<T: Serialize + Send + 'static> {
     pub routing_key: String,
     pub payload: T,
+    /// Trace the message is published in, sent to consumers in the message headers
+    pub context: TraceContext,
 }
 
+impl<T: Serialize + Send + 'static> Publish<T> {
+    /// Publishes within the current trace, call it from the route handling the request
+    pub fn new(routing_key: &str, payload: T) -> Self {
+        Publish {
+            routing_key: routing_key.to_string(),
+            payload,
+            context: TraceContext::current(),
+        }
+    }
+}
+
+/// Stops the publisher once every publish queued before it has completed
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Shutdown;
+
+/// Publishes are atomic, the next message is only handled once the broker has
+/// taken the previous one, so events keep their order and `Shutdown` flushes them.
 impl<T> Handler<Publish<T>> for Publisher
 where
     T: Serialize + Send + 'static,
 {
-    type Result = ResponseActFuture<Self, Result<(), ()>>;
+    type Result = AtomicResponse<Self, Result<(), ()>>;
 
     fn handle(&mut self, msg: Publish<T>, _ctx: &mut Context<Self>) -> Self::Result {
-        let channel = self.channel.clone();
+        let bus = self.bus.clone();
         let exchange = self.exchange.clone();
 
-        Box::pin(
+        let tracer = global::tracer(TRACER_NAME);
+        let span = tracer
+            .span_builder(format!("{} publish", msg.routing_key))
+            .with_kind(SpanKind::Producer)
+            .with_attributes([
+                KeyValue::new("messaging.system", "rabbitmq"),
+                KeyValue::new("messaging.destination.name", exchange.clone()),
+                KeyValue::new(
+                    "messaging.rabbitmq.destination.routing_key",
+                    msg.routing_key.clone(),
+                ),
+            ])
+            .start_with_context(&tracer, &msg.context);
+        let cx = msg.context.with_span(span);
+        let headers = trace_headers(&cx);
+
+        AtomicResponse::new(Box::pin(
             async move {
-                let data = serde_json::to_vec(&msg.payload).map_err(|_| ())?;
-                channel
-                    .basic_publish(
-                        &exchange,
-                        &msg.routing_key,
-                        BasicPublishOptions::default(),
-                        &data,
-                        BasicProperties::default(),
-                    )
-                    .await
-                    .map_err(|_| ())?
-                    .await
-                    .map_err(|_| ())?;
-                Ok(())
+                let result = async {
+                    let data = serde_json::to_vec(&msg.payload).map_err(|_| ())?;
+                    bus.publish(&exchange, &msg.routing_key, data, headers)
+                        .await
+                        .map_err(|e| warn!("Failed to publish {}: {}", msg.routing_key, e))?;
+                    Ok(())
+                }
+                .await;
+                record_published(&msg.routing_key, &result);
+                if result.is_err() {
+                    cx.span().set_status(Status::error("Publish failed"));
+                }
+                cx.span().end();
+                result
             }
             .into_actor(self),
-        )
+        ))
+    }
+}
+
+impl Handler<Shutdown> for Publisher {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Shutdown, ctx: &mut Context<Self>) -> Self::Result {
+        info!("Publisher flushed, stopping");
+        ctx.stop();
     }
 }
@@ -0,0 +1,23 @@
+use common::database::migrations::{Migration, SetMissingField};
+use common::models::tenant::DEFAULT_TENANT;
+
+/// Migrations of the Customer API in the order they run, append new ones
+/// with the next version and never change or remove an applied one
+pub fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(SetMissingField {
+            version: 1,
+            name: "customers_default_tenant",
+            collection: "customers",
+            field: "tenant_id",
+            value: DEFAULT_TENANT.into(),
+        }),
+        Box::new(SetMissingField {
+            version: 2,
+            name: "customers_initial_version",
+            collection: "customers",
+            field: "version",
+            value: 1i64.into(),
+        }),
+    ]
+}
@@ -0,0 +1,162 @@
+use crate::errors::api_error::ApiError;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use common::models::customer::Customer;
+use common::models::schema::{DateTimeSchema, ObjectIdSchema};
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Field customers are listed by, customers with the same value are ordered by id
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomerSort {
+    #[default]
+    CreatedAt,
+    Name,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Deserialize, IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+/// Query parameters of the customer listing, filters are combined
+pub struct CustomerQuery {
+    /// Customers whose name contains any of the words, case insensitive
+    pub name: Option<String>,
+    pub iso_country_code: Option<String>,
+    /// Primary phone number, exact match
+    pub phone: Option<String>,
+    /// Created at or after this RFC 3339 timestamp
+    pub created_from: Option<String>,
+    /// Created before this RFC 3339 timestamp
+    pub created_to: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: CustomerSort,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+    /// Page size from 1 to 100, defaults to 20
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page, the other parameters must not change between pages
+    pub cursor: Option<String>,
+    /// Omit addresses and contacts
+    #[serde(default)]
+    pub summary: bool,
+}
+
+/// Customers matching a listing, every field is optional
+#[derive(Debug, Default)]
+pub struct CustomerFilter {
+    pub name: Option<String>,
+    pub iso_country_code: Option<String>,
+    pub phone: Option<String>,
+    pub created_from: Option<DateTime>,
+    pub created_to: Option<DateTime>,
+}
+
+/// Position after the last customer of a page, sent to clients as an opaque string
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cursor {
+    pub sort: CustomerSort,
+    pub order: SortOrder,
+    pub name: String,
+    pub created_at: i64,
+    pub id: ObjectId,
+}
+
+impl Cursor {
+    /// Cursor continuing after the customer
+    pub fn after(customer: &Customer, sort: CustomerSort, order: SortOrder) -> Option<Self> {
+        Some(Cursor {
+            sort,
+            order,
+            name: customer.name.clone(),
+            created_at: customer.created_at.timestamp_millis(),
+            id: customer.id?,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decodes a cursor, it must come from a listing with the same sort and order
+    pub fn decode(cursor: &str, sort: CustomerSort, order: SortOrder) -> Result<Self, ApiError> {
+        let invalid = || ApiError::Validation("Invalid cursor".to_string());
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let cursor: Cursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        if cursor.sort != sort || cursor.order != order {
+            return Err(ApiError::Validation(
+                "Cursor belongs to a listing with a different sort or order".to_string(),
+            ));
+        }
+        Ok(cursor)
+    }
+}
+
+/// Page of a customer listing, as requested from a repository
+#[derive(Debug)]
+pub struct PageRequest {
+    pub sort: CustomerSort,
+    pub order: SortOrder,
+    pub limit: u32,
+    pub after: Option<Cursor>,
+    /// Addresses and contacts are not needed, repositories may leave them empty
+    pub summary: bool,
+}
+
+/// Customer without addresses and contacts
+#[derive(Serialize, ToSchema)]
+pub struct CustomerSummary {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    pub tenant_id: String,
+    pub primary_phone: String,
+    pub name: String,
+    pub iso_country_code: String,
+    #[schema(value_type = DateTimeSchema)]
+    pub created_at: DateTime,
+    #[schema(value_type = DateTimeSchema)]
+    pub updated_at: DateTime,
+}
+
+impl From<Customer> for CustomerSummary {
+    fn from(customer: Customer) -> Self {
+        CustomerSummary {
+            id: customer.id,
+            tenant_id: customer.tenant_id,
+            primary_phone: customer.primary_phone,
+            name: customer.name,
+            iso_country_code: customer.iso_country_code,
+            created_at: customer.created_at,
+            updated_at: customer.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum CustomerListItem {
+    Customer(Customer),
+    Summary(CustomerSummary),
+}
+
+/// A page of customers, `next_cursor` is absent on the last page
+#[derive(Serialize, ToSchema)]
+pub struct CustomerPage {
+    pub items: Vec<CustomerListItem>,
+    pub next_cursor: Option<String>,
+}
@@ -1,3 +1,4 @@
+use common::models::customer::Customer;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -5,3 +6,18 @@ pub struct CustomerCreated {
     pub id: String,
     pub name: String,
 }
+
+/// A changed field with its value before and after the change
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Published as `customer.updated` with the customer after the change
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomerUpdated {
+    pub customer: Customer,
+    pub changes: Vec<FieldChange>,
+}
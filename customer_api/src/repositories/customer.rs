@@ -0,0 +1,372 @@
+use crate::errors::api_error::ApiError;
+use crate::models::customer_query::{CustomerFilter, CustomerSort, PageRequest, SortOrder};
+use async_trait::async_trait;
+use common::database::indexes::IndexSpec;
+use common::models::customer::Customer;
+use common::models::tenant::tenant_filter;
+use common::services::metrics::time_mongo;
+use futures::TryStreamExt;
+use mongodb::bson::{Bson, Document, doc, oid::ObjectId};
+use mongodb::options::ReturnDocument;
+use mongodb::{Collection, Database};
+
+/// Arrays of sub-documents in a customer, each element has its own `_id`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubDocument {
+    Address,
+    Contact,
+}
+
+impl SubDocument {
+    pub fn field(self) -> &'static str {
+        match self {
+            SubDocument::Address => "addresses",
+            SubDocument::Contact => "contacts",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SubDocument::Address => "Address",
+            SubDocument::Contact => "Contact",
+        }
+    }
+
+    pub(crate) fn parse_id(self, id: &str) -> Result<ObjectId, ApiError> {
+        ObjectId::parse_str(id).map_err(|_| {
+            ApiError::Validation(format!("Invalid {} id {}", self.label().to_lowercase(), id))
+        })
+    }
+
+    pub(crate) fn not_found(self) -> ApiError {
+        ApiError::NotFound(format!("{} not found", self.label()))
+    }
+}
+
+/// Storage of customers, every lookup is scoped to a tenant and customers
+/// of other tenants are reported as not found
+#[async_trait]
+pub trait CustomerRepository: Send + Sync {
+    async fn create_customer(&self, new_customer: Customer) -> Result<Customer, ApiError>;
+
+    async fn get_customer(&self, tenant_id: &str, id: &str) -> Result<Customer, ApiError>;
+
+    /// Customers of the tenant matching the filter in the requested order,
+    /// starting after the page's cursor and returning at most `limit`
+    async fn list_customers(
+        &self,
+        tenant_id: &str,
+        filter: &CustomerFilter,
+        page: &PageRequest,
+    ) -> Result<Vec<Customer>, ApiError>;
+
+    /// Sets the fields of `update_doc` and returns the updated customer, only
+    /// when the customer is still at `version`, otherwise the precondition fails
+    async fn update_customer(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        update_doc: Document,
+    ) -> Result<Customer, ApiError>;
+
+    /// Appends an address or contact in a single update, concurrent appends are all kept
+    async fn push_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        kind: SubDocument,
+        element: Document,
+    ) -> Result<Customer, ApiError>;
+
+    /// Sets `fields` on the address or contact with the id, other fields keep their
+    /// values, only when the customer is still at `version`
+    async fn update_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+        fields: Document,
+    ) -> Result<Customer, ApiError>;
+
+    /// Removes the address or contact with the id, only when the customer is still at `version`
+    async fn remove_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+    ) -> Result<Customer, ApiError>;
+}
+
+pub struct MongoCustomerRepository {
+    collection: Collection<Customer>,
+}
+
+impl MongoCustomerRepository {
+    pub fn new(database: &Database) -> Self {
+        let collection: Collection<Customer> = database.collection("customers");
+
+        MongoCustomerRepository { collection }
+    }
+
+    /// Name search and lookups of addresses and contacts by their id
+    pub fn indexes() -> Vec<IndexSpec> {
+        vec![
+            IndexSpec::text("customers", "name_text", &["name"]),
+            IndexSpec::ascending("customers", "addresses_id", &["addresses._id"]),
+            IndexSpec::ascending("customers", "contacts_id", &["contacts._id"]),
+        ]
+    }
+}
+
+#[async_trait]
+impl CustomerRepository for MongoCustomerRepository {
+    async fn create_customer(&self, new_customer: Customer) -> Result<Customer, ApiError> {
+        let result = time_mongo(
+            self.collection.name(),
+            "insert_one",
+            self.collection.insert_one(new_customer),
+        )
+        .await?;
+        if let Some(inserted_id) = result.inserted_id.as_object_id() {
+            let filter = doc! { "_id": inserted_id };
+            if let Some(response) = time_mongo(
+                self.collection.name(),
+                "find_one",
+                self.collection.find_one(filter),
+            )
+            .await?
+            {
+                return Ok(response);
+            }
+        }
+        Err(ApiError::Internal("Failed to create customer".to_string()))
+    }
+
+    async fn get_customer(&self, tenant_id: &str, id: &str) -> Result<Customer, ApiError> {
+        let filter = doc! { "_id": parse_id(id)?, "tenant_id": tenant_filter(tenant_id) };
+        time_mongo(
+            self.collection.name(),
+            "find_one",
+            self.collection.find_one(filter),
+        )
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Customer not found".to_string()))
+    }
+
+    async fn list_customers(
+        &self,
+        tenant_id: &str,
+        filter: &CustomerFilter,
+        page: &PageRequest,
+    ) -> Result<Vec<Customer>, ApiError> {
+        let mut query = doc! { "tenant_id": tenant_filter(tenant_id) };
+        if let Some(name) = &filter.name {
+            query.insert("$text", doc! { "$search": name });
+        }
+        if let Some(iso_country_code) = &filter.iso_country_code {
+            query.insert("iso_country_code", iso_country_code);
+        }
+        if let Some(phone) = &filter.phone {
+            query.insert("primary_phone", phone);
+        }
+        let mut created_at = Document::new();
+        if let Some(created_from) = filter.created_from {
+            created_at.insert("$gte", created_from);
+        }
+        if let Some(created_to) = filter.created_to {
+            created_at.insert("$lt", created_to);
+        }
+        if !created_at.is_empty() {
+            query.insert("created_at", created_at);
+        }
+
+        let (field, direction, comparison) = match page.order {
+            SortOrder::Asc => (sort_field(page.sort), 1, "$gt"),
+            SortOrder::Desc => (sort_field(page.sort), -1, "$lt"),
+        };
+        if let Some(cursor) = &page.after {
+            let value = match page.sort {
+                CustomerSort::CreatedAt => {
+                    Bson::DateTime(mongodb::bson::DateTime::from_millis(cursor.created_at))
+                }
+                CustomerSort::Name => Bson::String(cursor.name.clone()),
+            };
+            query.insert(
+                "$or",
+                vec![
+                    doc! { field: { comparison: value.clone() } },
+                    doc! { field: value, "_id": { comparison: cursor.id } },
+                ],
+            );
+        }
+
+        let mut find = self
+            .collection
+            .find(query)
+            .sort(doc! { field: direction, "_id": direction })
+            .limit(i64::from(page.limit));
+        if page.summary {
+            find = find.projection(doc! { "addresses": 0, "contacts": 0 });
+        }
+        let cursor = time_mongo(self.collection.name(), "find", find).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn update_customer(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        update_doc: Document,
+    ) -> Result<Customer, ApiError> {
+        let filter = doc! {
+            "_id": parse_id(id)?,
+            "tenant_id": tenant_filter(tenant_id),
+            "version": version,
+        };
+        let update = doc! { "$set": update_doc, "$inc": { "version": 1 } };
+        match self.find_and_update(filter, update).await? {
+            Some(customer) => Ok(customer),
+            None => {
+                self.get_customer(tenant_id, id).await?;
+                Err(changed_since(version))
+            }
+        }
+    }
+
+    async fn push_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        kind: SubDocument,
+        element: Document,
+    ) -> Result<Customer, ApiError> {
+        let update = doc! {
+            "$push": { kind.field(): element },
+            "$set": { "updated_at": mongodb::bson::DateTime::now() },
+            "$inc": { "version": 1 },
+        };
+        self.update_customer_with(tenant_id, id, update).await
+    }
+
+    async fn update_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+        fields: Document,
+    ) -> Result<Customer, ApiError> {
+        let sub_id = kind.parse_id(sub_id)?;
+        // The positional `$` targets the element matched by the filter
+        let mut set = Document::new();
+        for (key, value) in fields {
+            set.insert(format!("{}.$.{}", kind.field(), key), value);
+        }
+        set.insert("updated_at", mongodb::bson::DateTime::now());
+        let update = doc! { "$set": set, "$inc": { "version": 1 } };
+        self.update_holding(tenant_id, id, version, kind, sub_id, update)
+            .await
+    }
+
+    async fn remove_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+    ) -> Result<Customer, ApiError> {
+        let sub_id = kind.parse_id(sub_id)?;
+        let update = doc! {
+            "$pull": { kind.field(): { "_id": sub_id } },
+            "$set": { "updated_at": mongodb::bson::DateTime::now() },
+            "$inc": { "version": 1 },
+        };
+        self.update_holding(tenant_id, id, version, kind, sub_id, update)
+            .await
+    }
+}
+
+impl MongoCustomerRepository {
+    /// Applies an update document to the customer and returns it as updated
+    async fn update_customer_with(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        update: Document,
+    ) -> Result<Customer, ApiError> {
+        let filter = doc! {
+            "_id": parse_id(id)?,
+            "tenant_id": tenant_filter(tenant_id),
+        };
+        self.find_and_update(filter, update)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Customer not found".to_string()))
+    }
+
+    /// Applies an update to the customer at `version` holding the sub-document, telling
+    /// a missing customer or a changed version apart from a missing address or contact
+    async fn update_holding(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: ObjectId,
+        update: Document,
+    ) -> Result<Customer, ApiError> {
+        let filter = doc! {
+            "_id": parse_id(id)?,
+            "tenant_id": tenant_filter(tenant_id),
+            "version": version,
+            format!("{}._id", kind.field()): sub_id,
+        };
+        if let Some(customer) = self.find_and_update(filter, update).await? {
+            return Ok(customer);
+        }
+        if self.get_customer(tenant_id, id).await?.version != version {
+            return Err(changed_since(version));
+        }
+        Err(kind.not_found())
+    }
+
+    /// Updates the customer matching the filter and returns it as written by this
+    /// update, so its version is the one the update produced, None when nothing matched
+    async fn find_and_update(
+        &self,
+        filter: Document,
+        update: Document,
+    ) -> Result<Option<Customer>, ApiError> {
+        Ok(time_mongo(
+            self.collection.name(),
+            "find_one_and_update",
+            self.collection
+                .find_one_and_update(filter, update)
+                .return_document(ReturnDocument::After),
+        )
+        .await?)
+    }
+}
+
+fn sort_field(sort: CustomerSort) -> &'static str {
+    match sort {
+        CustomerSort::CreatedAt => "created_at",
+        CustomerSort::Name => "name",
+    }
+}
+
+pub(crate) fn parse_id(id: &str) -> Result<ObjectId, ApiError> {
+    ObjectId::parse_str(id).map_err(|_| ApiError::Validation(format!("Invalid customer id {}", id)))
+}
+
+/// The customer exists but no longer has the version the update was based on
+pub(crate) fn changed_since(version: i64) -> ApiError {
+    ApiError::PreconditionFailed(format!("Customer has changed since version {}", version))
+}
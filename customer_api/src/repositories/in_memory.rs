@@ -0,0 +1,232 @@
+use crate::errors::api_error::ApiError;
+use crate::models::customer_query::{CustomerFilter, CustomerSort, PageRequest, SortOrder};
+use crate::repositories::customer::{CustomerRepository, SubDocument, changed_since, parse_id};
+use async_trait::async_trait;
+use common::models::customer::Customer;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{Bson, DateTime, Document, from_document, to_document};
+use std::cmp::Ordering;
+use std::sync::Mutex;
+
+/// Customers kept in memory, for tests and running without MongoDB
+#[derive(Default)]
+pub struct InMemoryCustomerRepository {
+    customers: Mutex<Vec<Customer>>,
+}
+
+#[async_trait]
+impl CustomerRepository for InMemoryCustomerRepository {
+    async fn create_customer(&self, mut new_customer: Customer) -> Result<Customer, ApiError> {
+        new_customer.id.get_or_insert_with(ObjectId::new);
+        self.customers.lock().unwrap().push(new_customer.clone());
+        Ok(new_customer)
+    }
+
+    async fn get_customer(&self, tenant_id: &str, id: &str) -> Result<Customer, ApiError> {
+        let id = parse_id(id)?;
+        let customers = self.customers.lock().unwrap();
+        customers
+            .iter()
+            .find(|customer| customer.id == Some(id) && customer.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| ApiError::NotFound("Customer not found".to_string()))
+    }
+
+    async fn list_customers(
+        &self,
+        tenant_id: &str,
+        filter: &CustomerFilter,
+        page: &PageRequest,
+    ) -> Result<Vec<Customer>, ApiError> {
+        let customers = self.customers.lock().unwrap();
+        let mut matching: Vec<&Customer> = customers
+            .iter()
+            .filter(|customer| customer.tenant_id == tenant_id && matches(customer, filter))
+            .collect();
+        matching.sort_by(|a, b| compare(a, b, page.sort));
+        if page.order == SortOrder::Desc {
+            matching.reverse();
+        }
+
+        let after = |customer: &&Customer| match &page.after {
+            None => true,
+            Some(cursor) => {
+                let position = match page.sort {
+                    CustomerSort::CreatedAt => customer
+                        .created_at
+                        .timestamp_millis()
+                        .cmp(&cursor.created_at),
+                    CustomerSort::Name => customer.name.as_str().cmp(&cursor.name),
+                }
+                .then(customer.id.cmp(&Some(cursor.id)));
+                match page.order {
+                    SortOrder::Asc => position == Ordering::Greater,
+                    SortOrder::Desc => position == Ordering::Less,
+                }
+            }
+        };
+        Ok(matching
+            .into_iter()
+            .filter(after)
+            .take(page.limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn update_customer(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        update_doc: Document,
+    ) -> Result<Customer, ApiError> {
+        let id = parse_id(id)?;
+        let mut customers = self.customers.lock().unwrap();
+        let customer = customers
+            .iter_mut()
+            .find(|customer| customer.id == Some(id) && customer.tenant_id == tenant_id)
+            .ok_or_else(|| ApiError::NotFound("Customer not found".to_string()))?;
+        if customer.version != version {
+            return Err(changed_since(version));
+        }
+
+        // Applies a `$set` of top level fields the way MongoDB would
+        let mut document = to_document(customer)?;
+        document.extend(update_doc);
+        document.insert("version", version + 1);
+        *customer = from_document(document)
+            .map_err(|e| ApiError::Internal(format!("Deserialization error: {}", e)))?;
+        Ok(customer.clone())
+    }
+
+    async fn push_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        kind: SubDocument,
+        element: Document,
+    ) -> Result<Customer, ApiError> {
+        self.modify_sub_documents(tenant_id, id, None, kind, |elements| {
+            elements.push(Bson::Document(element));
+            Ok(())
+        })
+    }
+
+    async fn update_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+        fields: Document,
+    ) -> Result<Customer, ApiError> {
+        let sub_id = kind.parse_id(sub_id)?;
+        self.modify_sub_documents(tenant_id, id, Some(version), kind, |elements| {
+            let element = elements
+                .iter_mut()
+                .filter_map(Bson::as_document_mut)
+                .find(|element| element.get_object_id("_id") == Ok(sub_id))
+                .ok_or_else(|| kind.not_found())?;
+            element.extend(fields);
+            Ok(())
+        })
+    }
+
+    async fn remove_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+    ) -> Result<Customer, ApiError> {
+        let sub_id = kind.parse_id(sub_id)?;
+        self.modify_sub_documents(tenant_id, id, Some(version), kind, |elements| {
+            let before = elements.len();
+            elements.retain(|element| {
+                element
+                    .as_document()
+                    .is_none_or(|element| element.get_object_id("_id") != Ok(sub_id))
+            });
+            if elements.len() == before {
+                return Err(kind.not_found());
+            }
+            Ok(())
+        })
+    }
+}
+
+impl InMemoryCustomerRepository {
+    /// Changes the addresses or contacts of a customer as documents, the way MongoDB would,
+    /// only when the customer is at `version` if one is given
+    fn modify_sub_documents(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: Option<i64>,
+        kind: SubDocument,
+        modify: impl FnOnce(&mut Vec<Bson>) -> Result<(), ApiError>,
+    ) -> Result<Customer, ApiError> {
+        let id = parse_id(id)?;
+        let mut customers = self.customers.lock().unwrap();
+        let customer = customers
+            .iter_mut()
+            .find(|customer| customer.id == Some(id) && customer.tenant_id == tenant_id)
+            .ok_or_else(|| ApiError::NotFound("Customer not found".to_string()))?;
+        if let Some(version) = version
+            && customer.version != version
+        {
+            return Err(changed_since(version));
+        }
+
+        let mut document = to_document(customer)?;
+        let elements = document
+            .get_array_mut(kind.field())
+            .map_err(|e| ApiError::Internal(format!("Invalid customer document: {}", e)))?;
+        modify(elements)?;
+        document.insert("updated_at", DateTime::now());
+        document.insert("version", customer.version + 1);
+        *customer = from_document(document)
+            .map_err(|e| ApiError::Internal(format!("Deserialization error: {}", e)))?;
+        Ok(customer.clone())
+    }
+}
+
+/// Name matching approximates a MongoDB text search, any whole word matches
+/// case insensitively but words are not stemmed
+fn matches(customer: &Customer, filter: &CustomerFilter) -> bool {
+    if let Some(name) = &filter.name {
+        let words: Vec<String> = customer
+            .name
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        if !name
+            .split_whitespace()
+            .any(|word| words.contains(&word.to_lowercase()))
+        {
+            return false;
+        }
+    }
+    filter
+        .iso_country_code
+        .as_ref()
+        .is_none_or(|code| &customer.iso_country_code == code)
+        && filter
+            .phone
+            .as_ref()
+            .is_none_or(|phone| &customer.primary_phone == phone)
+        && filter
+            .created_from
+            .is_none_or(|from| customer.created_at >= from)
+        && filter.created_to.is_none_or(|to| customer.created_at < to)
+}
+
+fn compare(a: &Customer, b: &Customer, sort: CustomerSort) -> Ordering {
+    match sort {
+        CustomerSort::CreatedAt => a.created_at.cmp(&b.created_at),
+        CustomerSort::Name => a.name.cmp(&b.name),
+    }
+    .then(a.id.cmp(&b.id))
+}
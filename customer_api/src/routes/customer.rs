@@ -1,104 +1,483 @@
-use crate::messaging::publisher::{Publish, Publisher};
-use crate::models::address::{Address, NewAddressRequest};
-use crate::models::contact::{Contact, NewContactRequest};
-use crate::models::customer::NewCustomerRequest;
-use crate::{handlers::customer::CustomerHandler, services::certification::CertificateService};
-use actix::Addr;
-use actix_web::{Error, HttpResponse, Responder, get, post, web};
-use actix_web_httpauth::extractors::bearer::BearerAuth;
-use axum::middleware::IntoMapRequestResult;
-
-use log::info;
-
-#[post("/customer/create")]
+use crate::errors::api_error::ApiError;
+use crate::handlers::customer::CustomerHandler;
+use crate::messaging::publisher::Publish;
+use crate::models::customer_query::{CustomerPage, CustomerQuery};
+use crate::models::messages::{CustomerUpdated, FieldChange};
+use actix::Recipient;
+use actix_web::http::header::{ETag, EntityTag, IfMatch};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, delete, get, patch, post, put, web};
+use common::errors::problem::ProblemDetails;
+use common::middleware::authentication::{AuthenticatedUser, RequireScope};
+use common::models::address::{Address, NewAddressRequest};
+use common::models::contact::{Contact, NewContactRequest};
+use common::models::customer::{
+    Customer, CustomerPatch, NewCustomerRequest, UpdateCustomerRequest,
+};
+use serde::Serialize;
+
+#[utoipa::path(
+    tag = "customer",
+    request_body = NewCustomerRequest,
+    responses(
+        (status = 200, description = "Customer created", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[post("/customer/create", wrap = "RequireScope(\"customer:manager\")")]
+/// Create a customer in the caller's tenant, requires "customer:manager" scope
 async fn create_customer(
     customer_handler: web::Data<CustomerHandler>,
-    cert_handler: web::Data<CertificateService>,
-    publisher: web::Data<Addr<Publisher>>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
     new_customer: web::Json<NewCustomerRequest>,
-    auth: BearerAuth,
-) -> Result<HttpResponse, Error> {
-    let token = auth.token();
-
-    match cert_handler.has_scope(token, "customer:manager") {
-        Ok(()) => match customer_handler
-            .create_customer(new_customer.into_inner())
-            .await
-        {
-            Ok(handler_response) => {
-                let msg = Publish {
-                    routing_key: "customer.created".to_string(),
-                    payload: handler_response.clone(),
-                };
-                if let Err(_) = publisher.send(msg).await {
-                    return Ok(HttpResponse::InternalServerError().finish());
-                }
-                Ok(HttpResponse::Ok().json(handler_response))
-            }
-            Err(err_msg) => Ok(HttpResponse::InternalServerError().body(err_msg)),
-        },
-        Err(resp) => Ok(resp),
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let handler_response = customer_handler
+        .create_customer(&user.tenant_id, new_customer.into_inner())
+        .await?;
+    publish_customer(&publisher, "customer.created", handler_response).await
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(("id" = String, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Customer found", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer not found", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:read"]))
+)]
+#[get("/customer/{id}", wrap = "RequireScope(\"customer:read\")")]
+/// Get a customer of the caller's tenant, requires "customer:read" scope
+async fn get_customer(
+    customer_handler: web::Data<CustomerHandler>,
+    id: web::Path<String>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let handler_response = customer_handler
+        .get_customer(&user.tenant_id, id.as_str())
+        .await?;
+    Ok(customer_response(handler_response))
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(
+        ("id" = String, Path, description = "Customer id"),
+        ("If-Match" = String, Header, description = "ETag of the customer the change is based on")
+    ),
+    request_body = UpdateCustomerRequest,
+    responses(
+        (status = 200, description = "Customer updated", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer id, fields or If-Match", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Customer has changed since the If-Match version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[put("/customer/{id}", wrap = "RequireScope(\"customer:manager\")")]
+/// Replace a customer's name, phone and country code, requires "customer:manager" scope
+async fn update_customer(
+    customer_handler: web::Data<CustomerHandler>,
+    publisher: web::Data<Recipient<Publish<CustomerUpdated>>>,
+    id: web::Path<String>,
+    update_request: web::Json<UpdateCustomerRequest>,
+    request: HttpRequest,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let (customer, changes) = customer_handler
+        .update_customer(
+            &user.tenant_id,
+            id.as_str(),
+            expected_version(&request)?,
+            update_request.into_inner(),
+        )
+        .await?;
+    publish_update(&publisher, customer, changes).await
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(
+        ("id" = String, Path, description = "Customer id"),
+        ("If-Match" = String, Header, description = "ETag of the customer the change is based on")
+    ),
+    request_body(content = CustomerPatch, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Customer updated", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer id, fields or If-Match", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Customer has changed since the If-Match version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[patch("/customer/{id}", wrap = "RequireScope(\"customer:manager\")")]
+/// Change some of a customer's fields with a JSON Merge Patch, requires "customer:manager" scope
+async fn patch_customer(
+    customer_handler: web::Data<CustomerHandler>,
+    publisher: web::Data<Recipient<Publish<CustomerUpdated>>>,
+    id: web::Path<String>,
+    patch: web::Json<CustomerPatch>,
+    request: HttpRequest,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let (customer, changes) = customer_handler
+        .patch_customer(
+            &user.tenant_id,
+            id.as_str(),
+            expected_version(&request)?,
+            patch.into_inner(),
+        )
+        .await?;
+    publish_update(&publisher, customer, changes).await
+}
+
+/// Publishes `customer.updated` when a field changed and responds with the customer
+async fn publish_update(
+    publisher: &Recipient<Publish<CustomerUpdated>>,
+    customer: Customer,
+    changes: Vec<FieldChange>,
+) -> Result<HttpResponse, ApiError> {
+    if !changes.is_empty() {
+        let event = CustomerUpdated {
+            customer: customer.clone(),
+            changes,
+        };
+        publish(publisher, "customer.updated", event).await?;
     }
+    Ok(customer_response(customer))
 }
 
-#[post("/address/add/{id}")]
+/// Publishes the customer under `routing_key` and responds with it
+async fn publish_customer(
+    publisher: &Recipient<Publish<Customer>>,
+    routing_key: &str,
+    customer: Customer,
+) -> Result<HttpResponse, ApiError> {
+    publish(publisher, routing_key, customer.clone()).await?;
+    Ok(customer_response(customer))
+}
+
+/// Sends an event to the publisher and waits until the broker has confirmed it.
+/// The change is already stored when this fails, the error says so because a
+/// retry with the old ETag would only be refused.
+async fn publish<T: Serialize + Send + 'static>(
+    publisher: &Recipient<Publish<T>>,
+    routing_key: &str,
+    event: T,
+) -> Result<(), ApiError> {
+    match publisher.send(Publish::new(routing_key, event)).await {
+        Ok(Ok(())) => Ok(()),
+        _ => Err(ApiError::Upstream(
+            "Change stored but its event was not published, read the customer for its ETag"
+                .to_string(),
+        )),
+    }
+}
+
+/// Responds with the customer and its version as the ETag
+fn customer_response(customer: Customer) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(ETag(EntityTag::new_strong(customer.version.to_string())))
+        .json(customer)
+}
+
+/// Version named by the If-Match header, changing a customer requires one so
+/// that a change based on an outdated read is refused instead of overwriting
+fn expected_version(request: &HttpRequest) -> Result<i64, ApiError> {
+    match request.get_header::<IfMatch>() {
+        Some(IfMatch::Items(tags)) => tags
+            .iter()
+            .find(|tag| !tag.weak)
+            .and_then(|tag| tag.tag().parse().ok())
+            .ok_or_else(|| {
+                ApiError::PreconditionFailed(
+                    "If-Match does not name a version of the customer".to_string(),
+                )
+            }),
+        Some(IfMatch::Any) => Err(ApiError::Validation(
+            "If-Match: * is not supported, send the customer's ETag".to_string(),
+        )),
+        None => Err(ApiError::PreconditionRequired(
+            "If-Match with the customer's ETag is required".to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(CustomerQuery),
+    responses(
+        (status = 200, description = "Page of customers", body = CustomerPage),
+        (status = 400, description = "Invalid filter, limit or cursor", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:read"]))
+)]
+#[get("/customers", wrap = "RequireScope(\"customer:read\")")]
+/// List and search the customers of the caller's tenant a page at a time, requires "customer:read" scope
+async fn list_customers(
+    customer_handler: web::Data<CustomerHandler>,
+    query: web::Query<CustomerQuery>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let page = customer_handler
+        .list_customers(&user.tenant_id, query.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(("id" = String, Path, description = "Customer id")),
+    request_body = NewAddressRequest,
+    responses(
+        (status = 200, description = "Customer with the address added", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[post("/address/add/{id}", wrap = "RequireScope(\"customer:manager\")")]
+/// Add an address to a customer, requires "customer:manager" scope
 async fn add_address(
     customer_handler: web::Data<CustomerHandler>,
-    cert_handler: web::Data<CertificateService>,
     address_request: web::Json<NewAddressRequest>,
-    publisher: web::Data<Addr<Publisher>>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
     id: web::Path<String>,
-    auth: BearerAuth,
-) -> Result<HttpResponse, Error> {
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
     let id_ref = id.as_str();
-    let token = auth.token();
     let address = Address::create_new(address_request.into_inner());
-    match cert_handler.has_scope(token, "customer:manager") {
-        Ok(()) => match customer_handler.add_address(id_ref, address).await {
-            Ok(handler_response) => {
-                let msg = Publish {
-                    routing_key: "customer.address.added".to_string(),
-                    payload: handler_response.clone(),
-                };
-                if let Err(_) = publisher.send(msg).await {
-                    return Ok(HttpResponse::InternalServerError().finish());
-                }
-                Ok(HttpResponse::Ok().json(handler_response))
-            }
-            Err(err_msg) => Ok(HttpResponse::InternalServerError().body(err_msg)),
-        },
-        Err(resp) => Ok(resp),
-    }
+    let handler_response = customer_handler
+        .add_address(&user.tenant_id, id_ref, address)
+        .await?;
+    publish_customer(&publisher, "customer.address.added", handler_response).await
 }
 
-#[post("/contact/add/{id}")]
+#[utoipa::path(
+    tag = "customer",
+    params(("id" = String, Path, description = "Customer id")),
+    request_body = NewContactRequest,
+    responses(
+        (status = 200, description = "Customer with the contact added", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[post("/contact/add/{id}", wrap = "RequireScope(\"customer:manager\")")]
+/// Add a contact to a customer, requires "customer:manager" scope
 async fn add_contact(
     customer_handler: web::Data<CustomerHandler>,
-    cert_handler: web::Data<CertificateService>,
     contact_request: web::Json<NewContactRequest>,
-    publisher: web::Data<Addr<Publisher>>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
     id: web::Path<String>,
-    auth: BearerAuth,
-) -> Result<HttpResponse, Error> {
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
     let id_ref = id.as_str();
-    let token = auth.token();
-
     let contact = Contact::create_new(contact_request.into_inner());
 
-    match cert_handler.has_scope(token, "customer:manager") {
-        Ok(()) => match customer_handler.add_contact(id_ref, contact).await {
-            Ok(handler_response) => {
-                let msg = Publish {
-                    routing_key: "customer.contact.added".to_string(),
-                    payload: handler_response.clone(),
-                };
-                if let Err(_) = publisher.send(msg).await {
-                    return Ok(HttpResponse::InternalServerError().finish());
-                }
-                Ok(HttpResponse::Ok().json(handler_response))
-            }
-            Err(err_msg) => Ok(HttpResponse::InternalServerError().body(err_msg)),
-        },
-        Err(resp) => Ok(resp),
-    }
+    let handler_response = customer_handler
+        .add_contact(&user.tenant_id, id_ref, contact)
+        .await?;
+    publish_customer(&publisher, "customer.contact.added", handler_response).await
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(
+        ("id" = String, Path, description = "Customer id"),
+        ("address_id" = String, Path, description = "Address id"),
+        ("If-Match" = String, Header, description = "ETag of the customer the change is based on")
+    ),
+    request_body = NewAddressRequest,
+    responses(
+        (status = 200, description = "Customer with the address updated", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer or address id or If-Match", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer or address not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Customer has changed since the If-Match version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[put(
+    "/address/update/{id}/{address_id}",
+    wrap = "RequireScope(\"customer:manager\")"
+)]
+/// Replace the fields of a customer's address, requires "customer:manager" scope
+async fn update_address(
+    customer_handler: web::Data<CustomerHandler>,
+    address_request: web::Json<NewAddressRequest>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
+    path: web::Path<(String, String)>,
+    request: HttpRequest,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let (id, address_id) = path.into_inner();
+    let handler_response = customer_handler
+        .update_address(
+            &user.tenant_id,
+            &id,
+            expected_version(&request)?,
+            &address_id,
+            address_request.into_inner(),
+        )
+        .await?;
+    publish_customer(&publisher, "customer.address.updated", handler_response).await
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(
+        ("id" = String, Path, description = "Customer id"),
+        ("address_id" = String, Path, description = "Address id"),
+        ("If-Match" = String, Header, description = "ETag of the customer the change is based on")
+    ),
+    responses(
+        (status = 200, description = "Customer with the address removed", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer or address id or If-Match", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer or address not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Customer has changed since the If-Match version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[delete(
+    "/address/remove/{id}/{address_id}",
+    wrap = "RequireScope(\"customer:manager\")"
+)]
+/// Remove an address from a customer, requires "customer:manager" scope
+async fn remove_address(
+    customer_handler: web::Data<CustomerHandler>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
+    path: web::Path<(String, String)>,
+    request: HttpRequest,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let (id, address_id) = path.into_inner();
+    let handler_response = customer_handler
+        .remove_address(
+            &user.tenant_id,
+            &id,
+            expected_version(&request)?,
+            &address_id,
+        )
+        .await?;
+    publish_customer(&publisher, "customer.address.removed", handler_response).await
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(
+        ("id" = String, Path, description = "Customer id"),
+        ("contact_id" = String, Path, description = "Contact id"),
+        ("If-Match" = String, Header, description = "ETag of the customer the change is based on")
+    ),
+    request_body = NewContactRequest,
+    responses(
+        (status = 200, description = "Customer with the contact updated", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer or contact id or If-Match", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer or contact not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Customer has changed since the If-Match version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[put(
+    "/contact/update/{id}/{contact_id}",
+    wrap = "RequireScope(\"customer:manager\")"
+)]
+/// Replace the fields of a customer's contact, requires "customer:manager" scope
+async fn update_contact(
+    customer_handler: web::Data<CustomerHandler>,
+    contact_request: web::Json<NewContactRequest>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
+    path: web::Path<(String, String)>,
+    request: HttpRequest,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let (id, contact_id) = path.into_inner();
+    let handler_response = customer_handler
+        .update_contact(
+            &user.tenant_id,
+            &id,
+            expected_version(&request)?,
+            &contact_id,
+            contact_request.into_inner(),
+        )
+        .await?;
+    publish_customer(&publisher, "customer.contact.updated", handler_response).await
+}
+
+#[utoipa::path(
+    tag = "customer",
+    params(
+        ("id" = String, Path, description = "Customer id"),
+        ("contact_id" = String, Path, description = "Contact id"),
+        ("If-Match" = String, Header, description = "ETag of the customer the change is based on")
+    ),
+    responses(
+        (status = 200, description = "Customer with the contact removed", body = Customer, headers(("ETag" = String, description = "Version of the customer"))),
+        (status = 400, description = "Invalid customer or contact id or If-Match", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Customer or contact not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "Customer has changed since the If-Match version", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "If-Match is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Missing scope", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Change stored but its event could not be published", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    security(("bearer_auth" = ["customer:manager"]))
+)]
+#[delete(
+    "/contact/remove/{id}/{contact_id}",
+    wrap = "RequireScope(\"customer:manager\")"
+)]
+/// Remove a contact from a customer, requires "customer:manager" scope
+async fn remove_contact(
+    customer_handler: web::Data<CustomerHandler>,
+    publisher: web::Data<Recipient<Publish<Customer>>>,
+    path: web::Path<(String, String)>,
+    request: HttpRequest,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, ApiError> {
+    let (id, contact_id) = path.into_inner();
+    let handler_response = customer_handler
+        .remove_contact(
+            &user.tenant_id,
+            &id,
+            expected_version(&request)?,
+            &contact_id,
+        )
+        .await?;
+    publish_customer(&publisher, "customer.contact.removed", handler_response).await
 }
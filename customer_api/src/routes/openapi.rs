@@ -0,0 +1,31 @@
+use crate::routes::customer;
+use common::routes::{health, metrics};
+use common::utils::openapi::BearerSecurity;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "customer_api",
+        description = "Manages customers with their addresses and contacts, changes are published as events"
+    ),
+    paths(
+        customer::create_customer,
+        customer::get_customer,
+        customer::list_customers,
+        customer::update_customer,
+        customer::patch_customer,
+        customer::add_address,
+        customer::update_address,
+        customer::remove_address,
+        customer::add_contact,
+        customer::update_contact,
+        customer::remove_contact,
+        health::live,
+        health::ready,
+        metrics::metrics,
+    ),
+    modifiers(&BearerSecurity)
+)]
+/// OpenAPI document of the customer_api routes
+pub struct ApiDoc;
@@ -0,0 +1,617 @@
+use actix::prelude::*;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH};
+use actix_web::{App, test, web};
+use common::messaging::bus::{ExchangeKind, MessageBus};
+use common::messaging::in_memory::InMemoryMessageBus;
+use common::models::customer::Customer;
+use common::services::certification::CertificateService;
+use customer_api::app::configure;
+use customer_api::handlers::customer::CustomerHandler;
+use customer_api::messaging::publisher::{Publish, Publisher};
+use customer_api::models::messages::CustomerUpdated;
+use customer_api::repositories::in_memory::InMemoryCustomerRepository;
+use jsonwebtoken::Algorithm;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const EXCHANGE: &str = "customer_events";
+const QUEUE: &str = "customer_events";
+
+/// In-process bus with a queue receiving every customer event
+async fn message_bus() -> InMemoryMessageBus {
+    let bus = InMemoryMessageBus::new();
+    bus.declare_exchange(EXCHANGE, ExchangeKind::Topic)
+        .await
+        .unwrap();
+    bus.declare_queue(QUEUE).await.unwrap();
+    bus.bind_queue(QUEUE, EXCHANGE, "customer.#").await.unwrap();
+    bus
+}
+
+fn publisher(bus: &InMemoryMessageBus) -> Addr<Publisher> {
+    Publisher {
+        bus: Arc::new(bus.clone()),
+        exchange: EXCHANGE.to_string(),
+    }
+    .start()
+}
+
+/// Routing keys and payloads of the events waiting in the queue, in publish order
+async fn published_events(bus: &InMemoryMessageBus) -> Vec<(String, Value)> {
+    let mut subscription = bus.subscribe(QUEUE, "test").await.unwrap();
+    let mut events = Vec::new();
+    for _ in 0..bus.queue_len(QUEUE) {
+        let delivery = subscription.next().await.unwrap().unwrap();
+        let payload = serde_json::from_slice(&delivery.payload).unwrap();
+        events.push((delivery.routing_key.clone(), payload));
+        delivery.ack().await.unwrap();
+    }
+    events
+}
+
+async fn published_routing_keys(bus: &InMemoryMessageBus) -> Vec<String> {
+    published_events(bus)
+        .await
+        .into_iter()
+        .map(|(routing_key, _)| routing_key)
+        .collect()
+}
+
+fn certificate_service() -> CertificateService {
+    let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    CertificateService::with_signing_key(
+        &format!("{}/private_key.pem", fixtures),
+        &format!("{}/public_key.pem", fixtures),
+        Algorithm::EdDSA,
+    )
+    .expect("Failed to load test keys")
+}
+
+fn token(tenant_id: &str, scopes: &[&str]) -> String {
+    certificate_service()
+        .create_token(
+            "686153021f8d0d5b45e82113",
+            tenant_id,
+            Some("manager@example.com".to_string()),
+            scopes.iter().map(|scope| scope.to_string()).collect(),
+        )
+        .unwrap()
+}
+
+fn app(
+    publisher: Addr<Publisher>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let customer_handler = CustomerHandler::new(Arc::new(InMemoryCustomerRepository::default()));
+    App::new()
+        .app_data(web::Data::new(customer_handler))
+        .app_data(web::Data::new(certificate_service()))
+        .app_data(web::Data::new(
+            publisher.clone().recipient::<Publish<Customer>>(),
+        ))
+        .app_data(web::Data::new(
+            publisher.recipient::<Publish<CustomerUpdated>>(),
+        ))
+        .configure(configure)
+}
+
+fn create_request(token: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri("/customer/create")
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .set_json(json!({
+            "name": "Space design house",
+            "primary_phone": "1234567890",
+            "iso_country_code": "US"
+        }))
+}
+
+#[actix_web::test]
+async fn customer_changes_are_stored_and_published() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let token = token("acme", &["customer:manager"]);
+
+    let customer: Value =
+        test::call_and_read_body_json(&app, create_request(&token).to_request()).await;
+    assert_eq!(customer["tenant_id"], "acme");
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/address/add/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .set_json(json!({
+            "street": "123 Main St",
+            "city": "Anytown",
+            "state": "CA",
+            "zip": "12345",
+            "country": "US",
+            "iso_code": "US"
+        }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["addresses"][0]["city"], "Anytown");
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/contact/add/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .set_json(json!({
+            "title": "Ms",
+            "name": "Ada Lovelace",
+            "email": "ada@example.com",
+            "phone": "1234567890",
+            "mobile": "0987654321"
+        }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["addresses"].as_array().unwrap().len(), 1);
+    assert_eq!(customer["contacts"][0]["name"], "Ada Lovelace");
+
+    assert_eq!(
+        published_routing_keys(&bus).await,
+        [
+            "customer.created",
+            "customer.address.added",
+            "customer.contact.added"
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn customers_are_scoped_to_the_tenant() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+
+    let request = create_request(&token("acme", &["customer:manager"])).to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let add_contact = |token: &str, id: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/contact/add/{}", id))
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .set_json(json!({
+                "title": "Mr",
+                "name": "Outsider",
+                "email": "outsider@example.com",
+                "phone": "1",
+                "mobile": "2"
+            }))
+            .to_request()
+    };
+    let other_tenant = token("globex", &["customer:manager"]);
+    let response = test::call_service(&app, add_contact(&other_tenant, id)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = test::call_service(&app, add_contact(&other_tenant, "not-an-id")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn managing_customers_requires_scope() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+
+    let request = create_request(&token("acme", &["user:read"])).to_request();
+    let error = test::try_call_service(&app, request)
+        .await
+        .err()
+        .expect("Missing scope should be refused");
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(bus.queue_len(QUEUE), 0);
+}
+
+#[actix_web::test]
+async fn customers_are_listed_a_page_at_a_time() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let manager = token("acme", &["customer:manager"]);
+    for (name, country) in [
+        ("Space design house", "US"),
+        ("Acme Tools", "GB"),
+        ("Globex Design", "US"),
+    ] {
+        let request = test::TestRequest::post()
+            .uri("/customer/create")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", manager)))
+            .set_json(json!({
+                "name": name,
+                "primary_phone": "1234567890",
+                "iso_country_code": country
+            }))
+            .to_request();
+        test::call_service(&app, request).await;
+    }
+
+    let reader = token("acme", &["customer:read"]);
+    let list = |query: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/customers?{}", query))
+            .insert_header((AUTHORIZATION, format!("Bearer {}", reader)))
+            .to_request()
+    };
+    let names = |page: &Value| -> Vec<String> {
+        page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|customer| customer["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let page: Value = test::call_and_read_body_json(&app, list("sort=name&limit=2")).await;
+    assert_eq!(names(&page), ["Acme Tools", "Globex Design"]);
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page: Value =
+        test::call_and_read_body_json(&app, list(&format!("sort=name&limit=2&cursor={}", cursor)))
+            .await;
+    assert_eq!(names(&page), ["Space design house"]);
+    assert!(page["next_cursor"].is_null());
+
+    let page: Value =
+        test::call_and_read_body_json(&app, list("name=design&sort=name&order=desc")).await;
+    assert_eq!(names(&page), ["Space design house", "Globex Design"]);
+
+    let page: Value =
+        test::call_and_read_body_json(&app, list("iso_country_code=GB&summary=true")).await;
+    assert_eq!(names(&page), ["Acme Tools"]);
+    assert!(page["items"][0].get("addresses").is_none());
+
+    let response =
+        test::call_service(&app, list(&format!("sort=created_at&cursor={}", cursor))).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = test::call_service(&app, list("limit=0")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn reading_customers_requires_read_scope() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let request = create_request(&token("acme", &["customer:manager"])).to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let get = |token: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/customer/{}", id))
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request()
+    };
+    let found: Value =
+        test::call_and_read_body_json(&app, get(&token("acme", &["customer:read"]))).await;
+    assert_eq!(found["name"], "Space design house");
+
+    let response = test::call_service(&app, get(&token("globex", &["customer:read"]))).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let error = test::try_call_service(&app, get(&token("acme", &["customer:manager"])))
+        .await
+        .err()
+        .expect("Missing scope should be refused");
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[actix_web::test]
+async fn customers_are_updated_and_patched() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let token = token("acme", &["customer:manager"]);
+    let customer: Value =
+        test::call_and_read_body_json(&app, create_request(&token).to_request()).await;
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/customer/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .insert_header((IF_MATCH, "\"1\""))
+        .set_json(json!({
+            "name": "Space Design House",
+            "primary_phone": "1234567890",
+            "iso_country_code": "US"
+        }))
+        .to_request();
+    let updated: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(updated["name"], "Space Design House");
+    assert_eq!(updated["version"], 2);
+
+    let patch = |version: &str, body: Value| {
+        test::TestRequest::patch()
+            .uri(&format!("/customer/{}", id))
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .insert_header((CONTENT_TYPE, "application/merge-patch+json"))
+            .insert_header((IF_MATCH, version.to_string()))
+            .set_payload(body.to_string())
+            .to_request()
+    };
+    let patched: Value =
+        test::call_and_read_body_json(&app, patch("\"2\"", json!({ "iso_country_code": "GB" })))
+            .await;
+    assert_eq!(patched["name"], "Space Design House");
+    assert_eq!(patched["iso_country_code"], "GB");
+    assert_eq!(patched["version"], 3);
+
+    // Unchanged values are not written or published
+    let unchanged: Value =
+        test::call_and_read_body_json(&app, patch("\"3\"", json!({ "iso_country_code": "GB" })))
+            .await;
+    assert_eq!(unchanged["version"], 3);
+
+    let events = published_events(&bus).await;
+    let updates: Vec<&Value> = events
+        .iter()
+        .filter(|(routing_key, _)| routing_key == "customer.updated")
+        .map(|(_, payload)| &payload["changes"])
+        .collect();
+    assert_eq!(
+        updates,
+        [
+            &json!([{ "field": "name", "old": "Space design house", "new": "Space Design House" }]),
+            &json!([{ "field": "iso_country_code", "old": "US", "new": "GB" }])
+        ]
+    );
+
+    for body in [
+        json!({ "name": null }),
+        json!({ "iso_country_code": "gbr" }),
+        json!({ "primary_phone": "call me" }),
+        json!({ "email": "unknown@example.com" }),
+    ] {
+        let response = test::call_service(&app, patch("\"3\"", body)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let request = test::TestRequest::patch()
+        .uri("/customer/686153021f8d0d5b45e82113")
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .insert_header((CONTENT_TYPE, "application/merge-patch+json"))
+        .insert_header((IF_MATCH, "\"1\""))
+        .set_payload(json!({ "name": "Nobody" }).to_string())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn addresses_and_contacts_are_updated_and_removed() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let token = token("acme", &["customer:manager"]);
+    let customer: Value =
+        test::call_and_read_body_json(&app, create_request(&token).to_request()).await;
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let address = |city: &str| {
+        json!({
+            "street": "123 Main St",
+            "city": city,
+            "state": "CA",
+            "zip": "12345",
+            "country": "US",
+            "iso_code": "US"
+        })
+    };
+    let request = test::TestRequest::post()
+        .uri(&format!("/address/add/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .set_json(address("Anytown"))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    let added = customer["addresses"][0].clone();
+    let address_id = added["_id"]["$oid"].as_str().unwrap();
+    assert_eq!(customer["version"], 2);
+
+    let update_address = |if_match: Option<&str>| {
+        let mut request = test::TestRequest::put()
+            .uri(&format!("/address/update/{}/{}", id, address_id))
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .set_json(address("Othertown"));
+        if let Some(if_match) = if_match {
+            request = request.insert_header((IF_MATCH, if_match.to_string()));
+        }
+        request.to_request()
+    };
+    let response = test::call_service(&app, update_address(None)).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+    let response = test::call_service(&app, update_address(Some("\"1\""))).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let customer: Value = test::call_and_read_body_json(&app, update_address(Some("\"2\""))).await;
+    let updated = &customer["addresses"][0];
+    assert_eq!(updated["city"], "Othertown");
+    assert_eq!(updated["_id"], added["_id"]);
+    assert_eq!(updated["created_at"], added["created_at"]);
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/contact/add/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .set_json(json!({
+            "title": "Ms",
+            "name": "Ada Lovelace",
+            "email": "ada@example.com",
+            "phone": "1234567890",
+            "mobile": "0987654321"
+        }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    let contact_id = customer["contacts"][0]["_id"]["$oid"].as_str().unwrap();
+
+    let request = test::TestRequest::put()
+        .uri(&format!("/contact/update/{}/{}", id, contact_id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .insert_header((IF_MATCH, "\"4\""))
+        .set_json(json!({
+            "title": "Dr",
+            "name": "Ada Lovelace",
+            "email": "ada@example.com",
+            "phone": "1234567890",
+            "mobile": "0987654321"
+        }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(customer["contacts"][0]["title"], "Dr");
+
+    let remove = |path: String, version: i64| {
+        test::TestRequest::delete()
+            .uri(&path)
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .insert_header((IF_MATCH, format!("\"{}\"", version)))
+            .to_request()
+    };
+    let response = test::call_service(
+        &app,
+        remove(format!("/contact/remove/{}/{}", id, contact_id), 4),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let customer: Value = test::call_and_read_body_json(
+        &app,
+        remove(format!("/address/remove/{}/{}", id, address_id), 5),
+    )
+    .await;
+    assert!(customer["addresses"].as_array().unwrap().is_empty());
+    let customer: Value = test::call_and_read_body_json(
+        &app,
+        remove(format!("/contact/remove/{}/{}", id, contact_id), 6),
+    )
+    .await;
+    assert!(customer["contacts"].as_array().unwrap().is_empty());
+    assert_eq!(customer["version"], 7);
+
+    let response = test::call_service(
+        &app,
+        remove(format!("/address/remove/{}/{}", id, address_id), 7),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response =
+        test::call_service(&app, remove(format!("/contact/remove/{}/not-an-id", id), 7)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(
+        published_routing_keys(&bus).await,
+        [
+            "customer.created",
+            "customer.address.added",
+            "customer.address.updated",
+            "customer.contact.added",
+            "customer.contact.updated",
+            "customer.address.removed",
+            "customer.contact.removed"
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn updates_require_the_current_version() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let token = token("acme", &["customer:manager", "customer:read"]);
+    let customer: Value =
+        test::call_and_read_body_json(&app, create_request(&token).to_request()).await;
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let get = test::TestRequest::get()
+        .uri(&format!("/customer/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .to_request();
+    let response = test::call_service(&app, get).await;
+    assert_eq!(response.headers().get(ETAG).unwrap(), "\"1\"");
+
+    let put = |if_match: Option<&str>, name: &str| {
+        let mut request = test::TestRequest::put()
+            .uri(&format!("/customer/{}", id))
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .set_json(json!({
+                "name": name,
+                "primary_phone": "1234567890",
+                "iso_country_code": "US"
+            }));
+        if let Some(if_match) = if_match {
+            request = request.insert_header((IF_MATCH, if_match.to_string()));
+        }
+        request.to_request()
+    };
+
+    let response = test::call_service(&app, put(None, "First")).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+    let response = test::call_service(&app, put(Some("*"), "First")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(
+        body["detail"],
+        "If-Match: * is not supported, send the customer's ETag"
+    );
+
+    let response = test::call_service(&app, put(Some("\"1\""), "First")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(ETAG).unwrap(), "\"2\"");
+
+    // A second writer still holding version 1 is refused instead of overwriting
+    let response = test::call_service(&app, put(Some("\"1\""), "Second")).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body["code"], "precondition_failed");
+    let response = test::call_service(&app, put(Some("W/\"2\""), "Second")).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let get = test::TestRequest::get()
+        .uri(&format!("/customer/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+        .to_request();
+    let stored: Value = test::call_and_read_body_json(&app, get).await;
+    assert_eq!(stored["name"], "First");
+    assert_eq!(stored["version"], 2);
+}
+
+#[actix_web::test]
+async fn unreadable_requests_are_problem_details() {
+    let bus = message_bus().await;
+    let app = test::init_service(app(publisher(&bus))).await;
+    let token = token("acme", &["customer:manager", "customer:read"]);
+    let bearer = (AUTHORIZATION, format!("Bearer {}", token));
+
+    let requests = [
+        test::TestRequest::post()
+            .uri("/customer/create")
+            .insert_header(bearer.clone())
+            .insert_header((CONTENT_TYPE, "application/json"))
+            .set_payload("{\"name\": "),
+        test::TestRequest::patch()
+            .uri("/customer/686153021f8d0d5b45e82113")
+            .insert_header(bearer.clone())
+            .insert_header((IF_MATCH, "\"1\""))
+            .insert_header((CONTENT_TYPE, "application/merge-patch+json"))
+            .set_payload("{\"nickname\": \"Acme\"}"),
+        test::TestRequest::get()
+            .uri("/customers?limit=abc")
+            .insert_header(bearer.clone()),
+    ];
+    for request in requests {
+        let response = test::call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        let problem: Value = test::read_body_json(response).await;
+        assert_eq!(problem["code"], "validation_failed");
+    }
+    assert!(published_routing_keys(&bus).await.is_empty());
+}
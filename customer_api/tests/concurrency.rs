@@ -0,0 +1,246 @@
+use async_trait::async_trait;
+use common::models::address::{Address, NewAddressRequest};
+use common::models::contact::{Contact, NewContactRequest};
+use common::models::customer::{Customer, NewCustomerRequest, UpdateCustomerRequest};
+use customer_api::errors::api_error::ApiError;
+use customer_api::handlers::customer::CustomerHandler;
+use customer_api::models::customer_query::{CustomerFilter, PageRequest};
+use customer_api::repositories::customer::{
+    CustomerRepository, MongoCustomerRepository, SubDocument,
+};
+use customer_api::repositories::in_memory::InMemoryCustomerRepository;
+use futures::future::join_all;
+use mongodb::bson::{Document, oid::ObjectId};
+use mongodb::{Client, Database};
+use std::sync::Arc;
+
+const TENANT: &str = "tenant-a";
+const PARALLEL: usize = 25;
+
+/// Yields to the runtime before every call, so concurrent requests interleave
+/// the way they do against a remote database. The in-memory repository applies
+/// each call atomically, so these tests guard the handler against going back to
+/// read-modify-write. The `$push` and version filters of the MongoDB repository
+/// are only exercised by the `_in_mongodb` tests, run when `MONGODB_TEST_URI`
+/// points at a server.
+#[derive(Default)]
+struct YieldingRepository {
+    inner: InMemoryCustomerRepository,
+}
+
+#[async_trait]
+impl CustomerRepository for YieldingRepository {
+    async fn create_customer(&self, new_customer: Customer) -> Result<Customer, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner.create_customer(new_customer).await
+    }
+
+    async fn get_customer(&self, tenant_id: &str, id: &str) -> Result<Customer, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner.get_customer(tenant_id, id).await
+    }
+
+    async fn list_customers(
+        &self,
+        tenant_id: &str,
+        filter: &CustomerFilter,
+        page: &PageRequest,
+    ) -> Result<Vec<Customer>, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner.list_customers(tenant_id, filter, page).await
+    }
+
+    async fn update_customer(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        update_doc: Document,
+    ) -> Result<Customer, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner
+            .update_customer(tenant_id, id, version, update_doc)
+            .await
+    }
+
+    async fn push_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        kind: SubDocument,
+        element: Document,
+    ) -> Result<Customer, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner
+            .push_sub_document(tenant_id, id, kind, element)
+            .await
+    }
+
+    async fn update_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+        fields: Document,
+    ) -> Result<Customer, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner
+            .update_sub_document(tenant_id, id, version, kind, sub_id, fields)
+            .await
+    }
+
+    async fn remove_sub_document(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        version: i64,
+        kind: SubDocument,
+        sub_id: &str,
+    ) -> Result<Customer, ApiError> {
+        tokio::task::yield_now().await;
+        self.inner
+            .remove_sub_document(tenant_id, id, version, kind, sub_id)
+            .await
+    }
+}
+
+fn yielding_handler() -> CustomerHandler {
+    CustomerHandler::new(Arc::new(YieldingRepository::default()))
+}
+
+/// A handler over a database of its own, dropped by the caller
+async fn mongo_handler() -> Option<(CustomerHandler, Database)> {
+    let Ok(uri) = std::env::var("MONGODB_TEST_URI") else {
+        eprintln!("MONGODB_TEST_URI is not set, skipping");
+        return None;
+    };
+    let database = Client::with_uri_str(uri)
+        .await
+        .unwrap()
+        .database(&format!("concurrency_{}", ObjectId::new().to_hex()));
+    let repository = MongoCustomerRepository::new(&database);
+    Some((CustomerHandler::new(Arc::new(repository)), database))
+}
+
+async fn customer(handler: &CustomerHandler) -> String {
+    let customer = handler
+        .create_customer(
+            TENANT,
+            NewCustomerRequest {
+                name: "Acme".to_string(),
+                primary_phone: "+45 12345678".to_string(),
+                iso_country_code: "DK".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    customer.id.unwrap().to_hex()
+}
+
+#[actix_web::test]
+async fn parallel_address_additions_are_all_kept() {
+    address_additions_are_all_kept(&yielding_handler()).await;
+}
+
+#[actix_web::test]
+async fn parallel_address_additions_are_all_kept_in_mongodb() {
+    let Some((handler, database)) = mongo_handler().await else {
+        return;
+    };
+    address_additions_are_all_kept(&handler).await;
+    database.drop().await.unwrap();
+}
+
+async fn address_additions_are_all_kept(handler: &CustomerHandler) {
+    let id = customer(handler).await;
+
+    let results = join_all((0..PARALLEL).map(|i| {
+        let address = Address::create_new(NewAddressRequest {
+            street: format!("Street {}", i),
+            city: "Aarhus".to_string(),
+            state: "Midtjylland".to_string(),
+            zip: "8000".to_string(),
+            country: "Denmark".to_string(),
+            iso_code: "DK".to_string(),
+        });
+        handler.add_address(TENANT, &id, address)
+    }))
+    .await;
+    assert!(results.iter().all(Result::is_ok));
+
+    let stored = handler.get_customer(TENANT, &id).await.unwrap();
+    assert_eq!(stored.addresses.len(), PARALLEL);
+    for i in 0..PARALLEL {
+        let street = format!("Street {}", i);
+        assert!(stored.addresses.iter().any(|a| a.street == street));
+    }
+}
+
+#[actix_web::test]
+async fn parallel_contact_additions_are_all_kept() {
+    let handler = yielding_handler();
+    let id = customer(&handler).await;
+
+    let results = join_all((0..PARALLEL).map(|i| {
+        let contact = Contact::create_new(NewContactRequest {
+            title: "Buyer".to_string(),
+            name: format!("Contact {}", i),
+            email: format!("contact{}@example.com", i),
+            phone: "+45 87654321".to_string(),
+            mobile: "+45 87654322".to_string(),
+        });
+        handler.add_contact(TENANT, &id, contact)
+    }))
+    .await;
+    assert!(results.iter().all(Result::is_ok));
+
+    let stored = handler.get_customer(TENANT, &id).await.unwrap();
+    assert_eq!(stored.contacts.len(), PARALLEL);
+}
+
+#[actix_web::test]
+async fn parallel_updates_of_one_version_keep_a_single_winner() {
+    updates_of_one_version_keep_a_single_winner(&yielding_handler()).await;
+}
+
+#[actix_web::test]
+async fn parallel_updates_of_one_version_keep_a_single_winner_in_mongodb() {
+    let Some((handler, database)) = mongo_handler().await else {
+        return;
+    };
+    updates_of_one_version_keep_a_single_winner(&handler).await;
+    database.drop().await.unwrap();
+}
+
+async fn updates_of_one_version_keep_a_single_winner(handler: &CustomerHandler) {
+    let id = customer(handler).await;
+
+    // Every update reads version 1 before any of them writes
+    let results = join_all((0..PARALLEL).map(|i| {
+        let request = UpdateCustomerRequest {
+            name: format!("Acme {}", i),
+            primary_phone: "+45 12345678".to_string(),
+            iso_country_code: "DK".to_string(),
+        };
+        handler.update_customer(TENANT, &id, 1, request)
+    }))
+    .await;
+    let winners: Vec<&Customer> = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .map(|(customer, _)| customer)
+        .collect();
+    assert_eq!(winners.len(), 1);
+    assert!(
+        results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .all(|error| matches!(error, ApiError::PreconditionFailed(_)))
+    );
+
+    let stored = handler.get_customer(TENANT, &id).await.unwrap();
+    assert_eq!(stored.version, 2);
+    assert_eq!(stored.name, winners[0].name);
+}
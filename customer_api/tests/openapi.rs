@@ -0,0 +1,24 @@
+use customer_api::routes::openapi::ApiDoc;
+use std::path::Path;
+use utoipa::OpenApi;
+
+/// Fails when the routes or models change without regenerating openapi.json,
+/// run with UPDATE_OPENAPI=1 to rewrite it.
+#[test]
+fn openapi_document_is_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+    let generated = ApiDoc::openapi()
+        .to_pretty_json()
+        .expect("Failed to serialize OpenAPI document")
+        + "\n";
+
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(&path, &generated).expect("Failed to write openapi.json");
+        return;
+    }
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "openapi.json is out of date, regenerate it with UPDATE_OPENAPI=1 cargo test -p customer_api --test openapi"
+    );
+}
@@ -5,22 +5,15 @@ use std::time::Instant;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
 
+type LoginResult = Result<LoginResponse, String>;
+
+#[derive(Default)]
 pub struct LoginScreen {
     email: String,
     password: String,
     show_error: bool,
     warning_shown_at: Option<Instant>,
 }
-impl Default for LoginScreen {
-    fn default() -> Self {
-        Self {
-            email: String::new(),
-            password: String::new(),
-            show_error: false,
-            warning_shown_at: None,
-        }
-    }
-}
 
 impl LoginScreen {
     pub fn show(
@@ -41,17 +34,15 @@ impl LoginScreen {
                 ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
                 ui.add_space(5.0);
                 //Error Label for missing fields, only shows for 3 seconds
-                if self.show_error {
-                    if let Some(warning_shown_at) = self.warning_shown_at {
-                        if warning_shown_at.elapsed().as_millis() < 3000 {
-                            ui.colored_label(
-                                egui::Color32::RED,
-                                "⚠️ Please enter both fields".to_string(),
-                            );
-                        } else {
-                            self.show_error = false;
-                            self.warning_shown_at = None;
-                        }
+                if let (true, Some(warning_shown_at)) = (self.show_error, self.warning_shown_at) {
+                    if warning_shown_at.elapsed().as_millis() < 3000 {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            "⚠️ Please enter both fields".to_string(),
+                        );
+                    } else {
+                        self.show_error = false;
+                        self.warning_shown_at = None;
                     }
                 }
                 ui.horizontal_centered(|ui| {
@@ -63,8 +54,8 @@ impl LoginScreen {
                                 self.password
                             );
                             let (tx, mut rx): (
-                                UnboundedSender<Result<LoginResponse, String>>,
-                                UnboundedReceiver<Result<LoginResponse, String>>,
+                                UnboundedSender<LoginResult>,
+                                UnboundedReceiver<LoginResult>,
                             ) = unbounded_channel();
 
                             auth_service.login_async(self.email.clone(), self.password.clone(), tx);
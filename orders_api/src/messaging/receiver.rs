@@ -0,0 +1,117 @@
+use common::messaging::bus::{Delivery, ExchangeKind, MessageBus, MessagingError};
+use common::messaging::trace_context::message_context;
+use common::services::metrics::record_consumed;
+use common::utils::load_settings::MessagingSettings;
+use common::utils::telemetry::{RequestId, TRACER_NAME};
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{KeyValue, global};
+use std::convert::Infallible;
+use std::fmt::Display;
+
+/// Declares the exchange the customer events are published to, the queue and
+/// its binding. The exchange is declared here too so the Orders API can start
+/// before the Customer API has declared it.
+pub async fn declare_topology(
+    bus: &dyn MessageBus,
+    messaging: &MessagingSettings,
+) -> Result<(), MessagingError> {
+    bus.declare_exchange(&messaging.exchange, ExchangeKind::Topic)
+        .await?;
+    bus.declare_queue(&messaging.queue).await?;
+    bus.bind_queue(
+        &messaging.queue,
+        &messaging.exchange,
+        &messaging.binding_key,
+    )
+    .await
+}
+
+/// Consumes messages from a queue until the connection is closed or `shutdown`
+/// resolves. Each delivery is passed to `handle` in a span continuing the
+/// publisher's trace, it is acknowledged when handled and rejected without
+/// requeueing when `handle` fails, so a bad message is not redelivered forever.
+/// On shutdown the consumer is cancelled after the current delivery is handled,
+/// the broker then stops delivering and requeues anything prefetched.
+pub async fn consume_queue<E: Display>(
+    bus: &dyn MessageBus,
+    queue_name: &str,
+    consumer_tag: &str,
+    handle: impl Fn(&Delivery) -> Result<(), E>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), MessagingError> {
+    let mut subscription = bus.subscribe(queue_name, consumer_tag).await?;
+
+    println!("📡 Waiting for messages...");
+
+    let tracer = global::tracer(TRACER_NAME);
+    tokio::pin!(shutdown);
+    loop {
+        let delivery = tokio::select! {
+            _ = &mut shutdown => {
+                println!("Cancelling consumer {}", consumer_tag);
+                return subscription.cancel().await;
+            }
+            delivery = subscription.next() => match delivery {
+                Some(delivery) => delivery,
+                None => return Ok(()),
+            },
+        };
+        let result = match delivery {
+            Ok(delivery) => {
+                let parent = message_context(&delivery.headers);
+                let span = tracer
+                    .span_builder(format!("{} process", delivery.routing_key))
+                    .with_kind(SpanKind::Consumer)
+                    .with_attributes([
+                        KeyValue::new("messaging.system", "rabbitmq"),
+                        KeyValue::new("messaging.destination.name", queue_name.to_string()),
+                        KeyValue::new(
+                            "messaging.rabbitmq.destination.routing_key",
+                            delivery.routing_key.clone(),
+                        ),
+                    ])
+                    .start_with_context(&tracer, &parent);
+                let cx = parent.with_span(span);
+
+                let request_id = cx
+                    .get::<RequestId>()
+                    .map(|RequestId(id)| id.as_str())
+                    .unwrap_or("-");
+                println!(
+                    "Routing key is: {} request_id={} trace_id={}",
+                    delivery.routing_key,
+                    request_id,
+                    cx.span().span_context().trace_id()
+                );
+
+                let result = match handle(&delivery) {
+                    Ok(()) => delivery.ack().await,
+                    Err(e) => {
+                        cx.span().set_status(Status::error(e.to_string()));
+                        eprintln!("❌ Rejected message: {}", e);
+                        delivery.nack(false).await
+                    }
+                };
+                if let Err(e) = &result {
+                    cx.span().set_status(Status::error(e.to_string()));
+                }
+                cx.span().end();
+                result
+            }
+            Err(e) => Err(e),
+        };
+        record_consumed(queue_name, &result);
+        if let Err(e) = result {
+            eprintln!("❌ Error: {:?}", e);
+        }
+    }
+}
+
+/// Prints the payload of a delivery, the orders service does not act on customer events yet
+pub fn print_message(delivery: &Delivery) -> Result<(), Infallible> {
+    println!(
+        "📨 Received: {}",
+        String::from_utf8_lossy(&delivery.payload)
+    );
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use common::routes::{health, metrics};
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "orders_api",
+        description = "Consumes customer events, the HTTP server only serves health checks and metrics"
+    ),
+    paths(health::live, health::ready, metrics::metrics)
+)]
+/// OpenAPI document of the orders_api routes
+pub struct ApiDoc;
@@ -0,0 +1,193 @@
+use actix::Actor;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{App, test, web};
+use common::messaging::bus::{Delivery, MessageBus};
+use common::messaging::in_memory::InMemoryMessageBus;
+use common::models::customer::Customer;
+use common::services::certification::CertificateService;
+use common::utils::load_settings::MessagingSettings;
+use customer_api::app::configure;
+use customer_api::handlers::customer::CustomerHandler;
+use customer_api::messaging::publisher::{Publish, Publisher};
+use customer_api::repositories::in_memory::InMemoryCustomerRepository;
+use jsonwebtoken::Algorithm;
+use orders_api::messaging::receiver::{consume_queue, declare_topology};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+const EXCHANGE: &str = "customer_events";
+const QUEUE: &str = "customer_queue";
+
+/// Declares what the Orders API declares at startup, before the Customer API runs
+async fn message_bus() -> InMemoryMessageBus {
+    let bus = InMemoryMessageBus::new();
+    let messaging = MessagingSettings::default();
+    assert_eq!(
+        (messaging.exchange.as_str(), messaging.queue.as_str()),
+        (EXCHANGE, QUEUE)
+    );
+    declare_topology(&bus, &messaging).await.unwrap();
+    bus
+}
+
+fn certificate_service() -> CertificateService {
+    let fixtures = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../customer_api/tests/fixtures"
+    );
+    CertificateService::with_signing_key(
+        &format!("{}/private_key.pem", fixtures),
+        &format!("{}/public_key.pem", fixtures),
+        Algorithm::EdDSA,
+    )
+    .expect("Failed to load test keys")
+}
+
+fn token() -> String {
+    certificate_service()
+        .create_token(
+            "686153021f8d0d5b45e82113",
+            "acme",
+            Some("manager@example.com".to_string()),
+            vec!["customer:manager".to_string()],
+        )
+        .unwrap()
+}
+
+/// Waits for the consumer to handle `count` messages
+async fn wait_for(received: &Mutex<Vec<String>>, count: usize) {
+    for _ in 0..100 {
+        if received.lock().unwrap().len() >= count {
+            return;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!(
+        "Expected {} messages, got {:?}",
+        count,
+        received.lock().unwrap()
+    );
+}
+
+#[actix_web::test]
+async fn customer_events_reach_the_orders_consumer() {
+    let bus = message_bus().await;
+    let publisher = Publisher {
+        bus: Arc::new(bus.clone()),
+        exchange: EXCHANGE.to_string(),
+    }
+    .start()
+    .recipient::<Publish<Customer>>();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(CustomerHandler::new(Arc::new(
+                InMemoryCustomerRepository::default(),
+            ))))
+            .app_data(web::Data::new(certificate_service()))
+            .app_data(web::Data::new(publisher))
+            .configure(configure),
+    )
+    .await;
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let (stop, stopped) = oneshot::channel::<()>();
+    let consumer = {
+        let bus = bus.clone();
+        let received = received.clone();
+        actix_web::rt::spawn(async move {
+            let handle = |delivery: &Delivery| {
+                let customer: Value = serde_json::from_slice(&delivery.payload)?;
+                received
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", delivery.routing_key, customer["name"]));
+                Ok::<_, serde_json::Error>(())
+            };
+            consume_queue(&bus, QUEUE, "orders", handle, async {
+                stopped.await.ok();
+            })
+            .await
+        })
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/customer/create")
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token())))
+        .set_json(json!({
+            "name": "Space design house",
+            "primary_phone": "1234567890",
+            "iso_country_code": "US"
+        }))
+        .to_request();
+    let customer: Value = test::call_and_read_body_json(&app, request).await;
+    let id = customer["_id"]["$oid"].as_str().unwrap();
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/address/add/{}", id))
+        .insert_header((AUTHORIZATION, format!("Bearer {}", token())))
+        .set_json(json!({
+            "street": "123 Main St",
+            "city": "Anytown",
+            "state": "CA",
+            "zip": "12345",
+            "country": "US",
+            "iso_code": "US"
+        }))
+        .to_request();
+    test::call_service(&app, request).await;
+
+    // "customer.#" matches any number of words, address events are routed too
+    wait_for(&received, 2).await;
+    assert_eq!(
+        *received.lock().unwrap(),
+        [
+            "customer.created \"Space design house\"",
+            "customer.address.added \"Space design house\""
+        ]
+    );
+    assert_eq!(bus.queue_len(QUEUE), 0);
+
+    stop.send(()).unwrap();
+    consumer.await.unwrap().unwrap();
+}
+
+#[actix_web::test]
+async fn messages_failing_to_be_handled_are_dropped() {
+    let bus = message_bus().await;
+    let (stop, stopped) = oneshot::channel::<()>();
+    let consumer = {
+        let bus = bus.clone();
+        actix_web::rt::spawn(async move {
+            let handle = |delivery: &Delivery| {
+                serde_json::from_slice::<Value>(&delivery.payload).map(|_| ())
+            };
+            consume_queue(&bus, QUEUE, "orders", handle, async {
+                stopped.await.ok();
+            })
+            .await
+        })
+    };
+
+    bus.publish(
+        EXCHANGE,
+        "customer.created",
+        b"not json".to_vec(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    for _ in 0..100 {
+        if bus.queue_len(QUEUE) == 0 {
+            break;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    // Give a requeued message the chance to show up again
+    actix_web::rt::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(bus.queue_len(QUEUE), 0);
+
+    stop.send(()).unwrap();
+    consumer.await.unwrap().unwrap();
+}